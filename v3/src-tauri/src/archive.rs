@@ -0,0 +1,187 @@
+// Safe zip extraction for archives from outside the app (.rtpack files opened through the
+// file association, the DLSS download). Entry names may not leave the output directory,
+// symlinks are refused and the archive must stay within fixed resource limits.
+use serde::Serialize;
+use std::fs::File;
+use std::io::{Read, Seek};
+use std::path::{Component, Path, PathBuf};
+
+use crate::ensure_dir;
+
+// Far above any real pack; these only stop archives built to exhaust the disk
+pub(crate) const MAX_ENTRIES: usize = 1_000;
+const MAX_TOTAL_SIZE: u64 = 2 * 1024 * 1024 * 1024;
+// Uncompressed size over compressed size for a single entry; material files compress
+// to roughly a quarter of their size
+const MAX_RATIO: u64 = 200;
+
+const S_IFMT: u32 = 0o170000;
+const S_IFLNK: u32 = 0o120000;
+
+// The entry's path relative to the output directory, or an error for names that could
+// escape it. Backslashes count as separators because the archive may come from Windows.
+pub(crate) fn entry_path(file: &zip::read::ZipFile) -> Result<PathBuf, String> {
+    let name = file.name();
+    let unsafe_name = || format!("Archive entry has an unsafe path: {}", name);
+    let normalized = name.replace('\\', "/");
+    if normalized.starts_with('/') || normalized.contains(':') || normalized.split('/').any(|part| part == "..") {
+        return Err(unsafe_name());
+    }
+    let path = file.enclosed_name().ok_or_else(unsafe_name)?;
+    if !path.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir)) {
+        return Err(unsafe_name());
+    }
+    Ok(path.to_path_buf())
+}
+
+pub(crate) fn is_symlink(file: &zip::read::ZipFile) -> bool {
+    file.unix_mode().is_some_and(|mode| mode & S_IFMT == S_IFLNK)
+}
+
+// Checks every entry against the limits from the archive directory, before anything is written
+pub(crate) fn check_archive<R: Read + Seek>(zip: &mut zip::ZipArchive<R>) -> Result<(), String> {
+    if zip.len() > MAX_ENTRIES {
+        return Err(format!("Archive has {} entries; the limit is {}", zip.len(), MAX_ENTRIES));
+    }
+    let mut total: u64 = 0;
+    for i in 0..zip.len() {
+        let file = zip.by_index_raw(i).map_err(|e| format!("Invalid archive: {e}"))?;
+        entry_path(&file)?;
+        if is_symlink(&file) {
+            return Err(format!("Archive entry is a symbolic link: {}", file.name()));
+        }
+        if file.size() > file.compressed_size().max(1).saturating_mul(MAX_RATIO) {
+            return Err(format!("Archive entry {} is compressed suspiciously well", file.name()));
+        }
+        total = total.saturating_add(file.size());
+        if total > MAX_TOTAL_SIZE {
+            return Err(format!("Archive unpacks to more than {} MB", MAX_TOTAL_SIZE / (1024 * 1024)));
+        }
+    }
+    Ok(())
+}
+
+// Reported after each extracted entry
+#[derive(Serialize, Debug, Clone)]
+pub(crate) struct ExtractProgress {
+    pub entry: String,
+    // 1-based position among the entries being extracted
+    pub index: usize,
+    pub total: usize,
+    pub bytes: u64,
+}
+
+// Extracts the entries whose path `wanted` accepts into `out_dir` after check_archive
+// passes, and returns the written files. Entries are streamed from the reader one at a
+// time. Sizes in the archive directory can lie, so the bytes written are limited too.
+pub(crate) fn extract_matching<R: Read + Seek>(
+    zip: &mut zip::ZipArchive<R>,
+    out_dir: &Path,
+    wanted: impl Fn(&Path) -> bool,
+    mut progress: impl FnMut(ExtractProgress),
+) -> Result<Vec<PathBuf>, String> {
+    check_archive(zip)?;
+    ensure_dir(out_dir).map_err(|e| e.to_string())?;
+    let mut selected = Vec::new();
+    for i in 0..zip.len() {
+        let file = zip.by_index_raw(i).map_err(|e| format!("Invalid archive: {e}"))?;
+        if !file.is_dir() && wanted(&entry_path(&file)?) {
+            selected.push(i);
+        }
+    }
+
+    let mut remaining = MAX_TOTAL_SIZE;
+    let mut written_files = Vec::new();
+    for (n, &i) in selected.iter().enumerate() {
+        let file = zip.by_index(i).map_err(|e| format!("Invalid archive: {e}"))?;
+        let outpath = out_dir.join(entry_path(&file)?);
+        if let Some(parent) = outpath.parent() {
+            ensure_dir(parent).map_err(|e| e.to_string())?;
+        }
+        let name = file.name().to_string();
+        let mut outfile = File::create(&outpath).map_err(|e| e.to_string())?;
+        let written = std::io::copy(&mut file.take(remaining + 1), &mut outfile).map_err(|e| e.to_string())?;
+        if written > remaining {
+            drop(outfile);
+            let _ = std::fs::remove_file(&outpath);
+            return Err(format!("Archive entry {} unpacks past the size limit", name));
+        }
+        remaining -= written;
+        written_files.push(outpath);
+        progress(ExtractProgress { entry: name, index: n + 1, total: selected.len(), bytes: written });
+    }
+    Ok(written_files)
+}
+
+pub(crate) fn extract_all<R: Read + Seek>(zip: &mut zip::ZipArchive<R>, out_dir: &Path) -> Result<Vec<PathBuf>, String> {
+    extract_matching(zip, out_dir, |_| true, |_| {})
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+    use zip::write::FileOptions;
+
+    fn archive(build: impl FnOnce(&mut zip::ZipWriter<Cursor<Vec<u8>>>)) -> zip::ZipArchive<Cursor<Vec<u8>>> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        build(&mut writer);
+        zip::ZipArchive::new(writer.finish().unwrap()).unwrap()
+    }
+
+    fn with_file(name: &str, contents: &[u8]) -> zip::ZipArchive<Cursor<Vec<u8>>> {
+        archive(|w| {
+            w.start_file(name, FileOptions::default()).unwrap();
+            w.write_all(contents).unwrap();
+        })
+    }
+
+    #[test]
+    fn rejects_paths_outside_the_output_directory() {
+        for name in ["../evil.dll", "materials/../../evil.dll", "..\\evil.dll", "/etc/evil", "C:/Windows/evil.dll"] {
+            let err = check_archive(&mut with_file(name, b"x")).unwrap_err();
+            assert!(err.contains("unsafe path"), "{name}: {err}");
+        }
+        assert!(check_archive(&mut with_file("materials/RTXStub.material.bin", b"x")).is_ok());
+    }
+
+    #[test]
+    fn rejects_symlinks_and_bombs() {
+        let mut link = archive(|w| w.add_symlink("RTXStub.material.bin", "/etc/passwd", FileOptions::default()).unwrap());
+        assert!(check_archive(&mut link).unwrap_err().contains("symbolic link"));
+
+        let mut bomb = with_file("RTXStub.material.bin", &vec![0u8; 4 * 1024 * 1024]);
+        assert!(check_archive(&mut bomb).unwrap_err().contains("compressed suspiciously well"));
+    }
+
+    #[test]
+    fn extracts_safe_archives() {
+        let dir = std::env::temp_dir().join(format!("brtx-archive-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut zip = archive(|w| {
+            w.add_directory("materials/", FileOptions::default()).unwrap();
+            w.start_file("materials/RTXStub.material.bin", FileOptions::default()).unwrap();
+            w.write_all(b"stub").unwrap();
+        });
+        extract_all(&mut zip, &dir).unwrap();
+        assert_eq!(std::fs::read_to_string(dir.join("materials").join("RTXStub.material.bin")).unwrap(), "stub");
+    }
+
+    #[test]
+    fn extracts_only_wanted_entries_with_progress() {
+        let dir = std::env::temp_dir().join(format!("brtx-archive-select-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut zip = archive(|w| {
+            for name in ["RTXStub.material.bin", "preview.png", "RTXPostFX.Bloom.material.bin"] {
+                w.start_file(name, FileOptions::default()).unwrap();
+                w.write_all(name.as_bytes()).unwrap();
+            }
+        });
+        let mut reports = Vec::new();
+        let written = extract_matching(&mut zip, &dir, |p| p.extension().is_some_and(|e| e == "bin"), |p| reports.push((p.index, p.total))).unwrap();
+
+        assert_eq!(written.len(), 2);
+        assert!(!dir.join("preview.png").exists());
+        assert_eq!(reports, vec![(1, 2), (2, 2)]);
+    }
+}
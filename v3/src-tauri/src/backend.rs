@@ -0,0 +1,341 @@
+// File-operation backends used to write into installations. Which backend is used
+// for a target is decided by select_backends so every install path behaves the same.
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+
+use crate::helper::{self, HelperBatch, HelperOp, HelperResult};
+use crate::powershell::{self, Command, Script};
+use crate::settings::load_settings;
+use crate::verify::{self, FileVerification};
+use crate::{ensure_dir, get_iobit_path_cached, is_sideloaded};
+
+#[async_trait]
+pub(crate) trait FileBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    // Removes every target; targets that do not exist are not an error
+    async fn delete(&self, targets: &[PathBuf]) -> Result<(), String>;
+
+    // Copies each (source, destination) pair, replacing existing destinations
+    async fn copy(&self, pairs: &[(PathBuf, PathBuf)]) -> Result<(), String>;
+
+    // Compares every destination with its source
+    async fn verify(&self, pairs: &[(PathBuf, PathBuf)]) -> Result<Vec<FileVerification>, String> {
+        verify::verify_files(pairs, load_settings().skip_file_verification).await
+    }
+
+    // Removes the existing targets, then copies and verifies the pairs. Deleting first
+    // matters because IObit Unlocker does not overwrite existing files.
+    async fn replace(&self, targets: &[PathBuf], pairs: &[(PathBuf, PathBuf)]) -> Result<Vec<FileVerification>, String> {
+        let existing: Vec<PathBuf> = targets.iter().filter(|t| t.exists()).cloned().collect();
+        self.delete(&existing).await?;
+        if pairs.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.copy(pairs).await?;
+        let results = self.verify(pairs).await?;
+        verify::failures(&results).map_or(Ok(results), Err)
+    }
+}
+
+// A backend that can be tried for a target, in the order select_backends returns them
+#[derive(Debug, Clone)]
+pub(crate) enum BackendChoice {
+    Direct,
+    IObit(PathBuf),
+    Elevated,
+}
+
+impl BackendChoice {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BackendChoice::Direct => "direct",
+            BackendChoice::IObit(_) => "iobit",
+            BackendChoice::Elevated => "elevated",
+        }
+    }
+
+    pub fn open(&self, app_handle: &tauri::AppHandle) -> Box<dyn FileBackend> {
+        match self {
+            BackendChoice::Direct => Box::new(DirectBackend),
+            BackendChoice::IObit(exe) => Box::new(IObitBackend { app_handle: app_handle.clone(), exe: exe.clone() }),
+            BackendChoice::Elevated => Box::new(ElevatedBackend { app_handle: app_handle.clone() }),
+        }
+    }
+}
+
+// WindowsApps is only writable through IObit Unlocker or elevation; anything else is written directly
+pub(crate) fn select_backends(target_dir: &Path) -> Vec<BackendChoice> {
+    if is_sideloaded(&target_dir.to_string_lossy()) {
+        return vec![BackendChoice::Direct];
+    }
+    let iobit = get_iobit_path_cached();
+    if iobit.is_none() {
+        println!("⚠ IObit Unlocker not found, using fallback method for WindowsApps");
+    }
+    protected_backends(iobit)
+}
+
+// IObit Unlocker when it is installed, then an elevated copy
+fn protected_backends(iobit: Option<PathBuf>) -> Vec<BackendChoice> {
+    iobit.map(BackendChoice::IObit).into_iter().chain([BackendChoice::Elevated]).collect()
+}
+
+// Copies through the first backend that succeeds and verifies the result, without
+// snapshots; use transaction::install_files for files that must be rolled back
+pub(crate) async fn copy_files(app_handle: &tauri::AppHandle, target_dir: &Path, pairs: &[(PathBuf, PathBuf)]) -> Result<Vec<FileVerification>, String> {
+    let mut last_err = None;
+    for choice in select_backends(target_dir) {
+        let backend = choice.open(app_handle);
+        match backend.replace(&[], pairs).await {
+            Ok(verification) => return Ok(verification),
+            Err(e) => {
+                println!("⚠ Copy via {} failed: {}", backend.name(), e);
+                last_err = Some(e);
+            }
+        }
+    }
+    Err(last_err.unwrap_or_else(|| "No file backend available".to_string()))
+}
+
+pub(crate) struct DirectBackend;
+
+#[async_trait]
+impl FileBackend for DirectBackend {
+    fn name(&self) -> &'static str {
+        "direct"
+    }
+
+    async fn delete(&self, targets: &[PathBuf]) -> Result<(), String> {
+        for target in targets {
+            if target.exists() {
+                println!("Removing existing file: {}", target.display());
+                fs::remove_file(target).map_err(|e| format!("Failed to remove {}: {e}", target.display()))?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn copy(&self, pairs: &[(PathBuf, PathBuf)]) -> Result<(), String> {
+        for (src, dest) in pairs {
+            if let Some(parent) = dest.parent() {
+                ensure_dir(parent).map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+            }
+            fs::copy(src, dest).map_err(|e| format!("Direct copy failed to {}: {e}", dest.display()))?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) struct IObitBackend {
+    app_handle: tauri::AppHandle,
+    exe: PathBuf,
+}
+
+impl IObitBackend {
+    // Runs IObit Unlocker with the same argument string as the PowerShell v2 implementation
+    async fn run(&self, arglist: &str) -> Result<tauri_plugin_shell::process::Output, String> {
+        let script = Script::new().start_process(
+            Command::new("Start-Process")
+                .param("-FilePath")
+                .path(&self.exe)
+                .param("-ArgumentList")
+                .literal(arglist),
+        );
+        powershell::run(&self.app_handle, &script).await
+    }
+}
+
+// IObit's own list syntax: "path1","path2"
+fn quoted_list(paths: &[PathBuf]) -> String {
+    paths.iter().map(|p| format!("\"{}\"", p.display())).collect::<Vec<_>>().join(",")
+}
+
+#[async_trait]
+impl FileBackend for IObitBackend {
+    fn name(&self) -> &'static str {
+        "iobit"
+    }
+
+    async fn delete(&self, targets: &[PathBuf]) -> Result<(), String> {
+        if targets.is_empty() {
+            return Ok(());
+        }
+        println!("Deleting {} file(s) via IObit (single pass)", targets.len());
+        let output = self.run(&format!("/Delete {}", quoted_list(targets))).await?;
+        if !output.status.success() {
+            // IObit reports failures for files that are already gone; the copy is verified afterwards
+            println!("IObit delete reported non-success: {}", String::from_utf8_lossy(&output.stderr));
+        }
+        Ok(())
+    }
+
+    async fn copy(&self, pairs: &[(PathBuf, PathBuf)]) -> Result<(), String> {
+        // IObit copies into a directory under the source's own name, so group by destination directory
+        let mut by_dir: BTreeMap<PathBuf, Vec<PathBuf>> = BTreeMap::new();
+        for (src, dest) in pairs {
+            if src.file_name() != dest.file_name() {
+                return Err(format!("IObit Unlocker cannot rename {} to {}", src.display(), dest.display()));
+            }
+            let dir = dest.parent().ok_or_else(|| format!("Invalid destination: {}", dest.display()))?;
+            by_dir.entry(dir.to_path_buf()).or_default().push(src.clone());
+        }
+        for (dir, sources) in by_dir {
+            // Best-effort ensure destination directory
+            let _ = ensure_dir(&dir);
+            println!("Copying {} file(s) via IObit (single pass) -> {}", sources.len(), dir.display());
+            let output = self.run(&format!("/Copy {} \"{}\"", quoted_list(&sources), dir.display())).await?;
+            if !output.status.success() {
+                return Err(format!("IObit copy via PowerShell failed: {}", String::from_utf8_lossy(&output.stderr)));
+            }
+        }
+        // IObit reports success even when it did nothing; callers verify the written files
+        Ok(())
+    }
+}
+
+pub(crate) struct ElevatedBackend {
+    app_handle: tauri::AppHandle,
+}
+
+impl ElevatedBackend {
+    // Runs the batch in the elevated helper; any failed operation fails the whole call
+    async fn run_batch(&self, operations: Vec<HelperOp>) -> Result<HelperResult, String> {
+        if operations.is_empty() {
+            return Ok(HelperResult::default());
+        }
+        let result = helper::run_elevated(&self.app_handle, &HelperBatch { operations }).await?;
+        match result.failures() {
+            Some(e) => Err(format!("Elevated helper failed: {}", e)),
+            None => Ok(result),
+        }
+    }
+}
+
+fn copy_ops(pairs: &[(PathBuf, PathBuf)]) -> impl Iterator<Item = HelperOp> + '_ {
+    pairs.iter().map(|(source, destination)| HelperOp::Copy { source: source.clone(), destination: destination.clone() })
+}
+
+// Every step of a replace as one helper batch, so it needs a single elevation
+fn replace_batch(existing: &[PathBuf], pairs: &[(PathBuf, PathBuf)], skip_verification: bool) -> Vec<HelperOp> {
+    let mut operations: Vec<HelperOp> = existing.iter().map(|path| HelperOp::Delete { path: path.clone() }).collect();
+    operations.extend(copy_ops(pairs));
+    if !skip_verification {
+        operations.extend(pairs.iter().map(|(source, destination)| HelperOp::Verify { source: source.clone(), destination: destination.clone() }));
+    }
+    operations
+}
+
+#[async_trait]
+impl FileBackend for ElevatedBackend {
+    fn name(&self) -> &'static str {
+        "elevated"
+    }
+
+    async fn delete(&self, targets: &[PathBuf]) -> Result<(), String> {
+        self.run_batch(targets.iter().map(|path| HelperOp::Delete { path: path.clone() }).collect()).await.map(|_| ())
+    }
+
+    async fn copy(&self, pairs: &[(PathBuf, PathBuf)]) -> Result<(), String> {
+        self.run_batch(copy_ops(pairs).collect()).await.map(|_| ())
+    }
+
+    // The copied files are verified inside the elevated helper as part of the same batch
+    async fn replace(&self, targets: &[PathBuf], pairs: &[(PathBuf, PathBuf)]) -> Result<Vec<FileVerification>, String> {
+        let skip = load_settings().skip_file_verification;
+        let existing: Vec<PathBuf> = targets.iter().filter(|t| t.exists()).cloned().collect();
+        let result = self.run_batch(replace_batch(&existing, pairs, skip)).await?;
+        if skip {
+            return verify::verify_files(pairs, true).await;
+        }
+        Ok(result.results.into_iter().filter_map(|r| r.verification).collect())
+    }
+}
+
+// Applies operations to the real filesystem like DirectBackend and records them,
+// so install pipelines can be exercised on any platform
+#[cfg(test)]
+pub(crate) mod fake {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub(crate) enum FileOp {
+        Delete(PathBuf),
+        Copy(PathBuf, PathBuf),
+    }
+
+    #[derive(Default)]
+    pub(crate) struct RecordingBackend {
+        pub ops: Mutex<Vec<FileOp>>,
+        // Makes the next copy write its first pair and then fail
+        pub fail_next_copy: AtomicBool,
+    }
+
+    impl RecordingBackend {
+        pub fn ops(&self) -> Vec<FileOp> {
+            self.ops.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl FileBackend for RecordingBackend {
+        fn name(&self) -> &'static str {
+            "recording"
+        }
+
+        async fn delete(&self, targets: &[PathBuf]) -> Result<(), String> {
+            self.ops.lock().unwrap().extend(targets.iter().cloned().map(FileOp::Delete));
+            DirectBackend.delete(targets).await
+        }
+
+        async fn copy(&self, pairs: &[(PathBuf, PathBuf)]) -> Result<(), String> {
+            self.ops.lock().unwrap().extend(pairs.iter().cloned().map(|(s, d)| FileOp::Copy(s, d)));
+            if self.fail_next_copy.swap(false, Ordering::SeqCst) {
+                DirectBackend.copy(&pairs[..pairs.len().min(1)]).await?;
+                return Err("simulated copy failure".to_string());
+            }
+            DirectBackend.copy(pairs).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(choices: &[BackendChoice]) -> Vec<&'static str> {
+        choices.iter().map(BackendChoice::name).collect()
+    }
+
+    #[test]
+    fn sideloaded_targets_are_written_directly() {
+        let choices = select_backends(Path::new(r"C:\Games\Minecraft\data\renderer\materials"));
+        assert_eq!(names(&choices), vec!["direct"]);
+    }
+
+    #[test]
+    fn windows_apps_targets_try_iobit_before_elevation() {
+        let exe = PathBuf::from(r"C:\Program Files (x86)\IObit\IObit Unlocker\IObitUnlocker.exe");
+        assert_eq!(names(&protected_backends(Some(exe))), vec!["iobit", "elevated"]);
+        assert_eq!(names(&protected_backends(None)), vec!["elevated"]);
+    }
+
+    #[test]
+    fn elevated_replace_is_one_batch() {
+        let old = PathBuf::from(r"C:\Program Files\WindowsApps\materials\RTXStub.material.bin");
+        let pair = (PathBuf::from(r"C:\brtx\RTXStub.material.bin"), old.clone());
+        let batch = replace_batch(std::slice::from_ref(&old), std::slice::from_ref(&pair), false);
+        assert_eq!(batch, vec![
+            HelperOp::Delete { path: old.clone() },
+            HelperOp::Copy { source: pair.0.clone(), destination: old.clone() },
+            HelperOp::Verify { source: pair.0.clone(), destination: old.clone() },
+        ]);
+        assert_eq!(replace_batch(&[], std::slice::from_ref(&pair), true).len(), 1);
+    }
+}
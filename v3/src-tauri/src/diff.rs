@@ -0,0 +1,126 @@
+// Per-file comparison of two material sets, for reviewing a new version of a pack
+// before installing it
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::material::{self, MaterialInfo};
+use crate::sha256_hex;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum FileChange {
+    Added,
+    Removed,
+    Unchanged,
+    Changed,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub(crate) struct FileSummary {
+    pub size: u64,
+    pub sha256: String,
+    // None when the file is not a readable material
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub material: Option<MaterialInfo>,
+}
+
+impl FileSummary {
+    fn of(data: &[u8]) -> Self {
+        FileSummary { size: data.len() as u64, sha256: sha256_hex(data), material: material::parse(data).ok() }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub(crate) struct HeaderChange {
+    pub field: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub(crate) struct MaterialDiff {
+    pub name: String,
+    pub change: FileChange,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before: Option<FileSummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<FileSummary>,
+    // Only for changed files that both parse as materials
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub header_changes: Vec<HeaderChange>,
+}
+
+fn header_changes(before: &MaterialInfo, after: &MaterialInfo) -> Vec<HeaderChange> {
+    let list = |items: &Option<Vec<String>>| items.as_ref().map(|items| items.join(", "));
+    let fields = [
+        ("version", Some(before.version.to_string()), Some(after.version.to_string())),
+        ("encryption", Some(before.encryption.clone()), Some(after.encryption.clone())),
+        ("name", before.name.clone(), after.name.clone()),
+        ("parent", before.parent.clone(), after.parent.clone()),
+        ("passes", list(&before.passes), list(&after.passes)),
+        ("platforms", Some(before.platforms.join(", ")), Some(after.platforms.join(", "))),
+    ];
+    fields
+        .into_iter()
+        .filter(|(_, before, after)| before != after)
+        .map(|(field, before, after)| HeaderChange { field: field.to_string(), before, after })
+        .collect()
+}
+
+// Compares two sets of files keyed by file name, sorted by name
+pub(crate) fn diff(before: &BTreeMap<String, Vec<u8>>, after: &BTreeMap<String, Vec<u8>>) -> Vec<MaterialDiff> {
+    let mut names: Vec<&String> = before.keys().chain(after.keys()).collect();
+    names.sort();
+    names.dedup();
+    names
+        .into_iter()
+        .map(|name| {
+            let before = before.get(name).map(|data| FileSummary::of(data));
+            let after = after.get(name).map(|data| FileSummary::of(data));
+            let change = match (&before, &after) {
+                (None, _) => FileChange::Added,
+                (_, None) => FileChange::Removed,
+                (Some(b), Some(a)) if b.sha256 == a.sha256 => FileChange::Unchanged,
+                _ => FileChange::Changed,
+            };
+            let header_changes = match (change, before.as_ref().and_then(|b| b.material.as_ref()), after.as_ref().and_then(|a| a.material.as_ref())) {
+                (FileChange::Changed, Some(b), Some(a)) => header_changes(b, a),
+                _ => Vec::new(),
+            };
+            MaterialDiff { name: name.clone(), change, before, after, header_changes }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::tests::material;
+
+    #[test]
+    fn classifies_files() {
+        let before = BTreeMap::from([
+            ("RTXStub.material.bin".to_string(), material(22, "RTXStub", None)),
+            ("RTXPostFX.Bloom.material.bin".to_string(), b"bloom".to_vec()),
+            ("RTXPostFX.Tonemapping.material.bin".to_string(), b"tonemapping".to_vec()),
+        ]);
+        let after = BTreeMap::from([
+            ("RTXStub.material.bin".to_string(), material(25, "RTXStub", Some("Core/Base"))),
+            ("RTXPostFX.Bloom.material.bin".to_string(), b"bloom".to_vec()),
+            ("RTXStub.Extra.material.bin".to_string(), b"extra".to_vec()),
+        ]);
+        let diffs = diff(&before, &after);
+        let changes: Vec<(&str, FileChange)> = diffs.iter().map(|d| (d.name.as_str(), d.change)).collect();
+        assert_eq!(changes, vec![
+            ("RTXPostFX.Bloom.material.bin", FileChange::Unchanged),
+            ("RTXPostFX.Tonemapping.material.bin", FileChange::Removed),
+            ("RTXStub.Extra.material.bin", FileChange::Added),
+            ("RTXStub.material.bin", FileChange::Changed),
+        ]);
+
+        assert_eq!(diffs[3].header_changes, vec![
+            HeaderChange { field: "version".to_string(), before: Some("22".to_string()), after: Some("25".to_string()) },
+            HeaderChange { field: "parent".to_string(), before: None, after: Some("Core/Base".to_string()) },
+        ]);
+    }
+}
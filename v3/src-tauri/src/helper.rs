@@ -0,0 +1,224 @@
+// Hidden helper mode of the installer binary. The elevated backend starts a copy of
+// the installer with `--elevated-helper <batch.json> <result.json>`; the helper runs
+// the batch of file operations with the elevated token and writes a per-operation
+// result file that the unelevated app reads back.
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::powershell::{self, Command, Script};
+use crate::verify::{self, FileVerification, VerificationStatus};
+use crate::{brtx_dir, ensure_dir, read_json_file, unique_name, write_json_file};
+
+pub(crate) const HELPER_FLAG: &str = "--elevated-helper";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub(crate) enum HelperOp {
+    Delete { path: PathBuf },
+    Copy { source: PathBuf, destination: PathBuf },
+    Verify { source: PathBuf, destination: PathBuf },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub(crate) struct HelperBatch {
+    pub operations: Vec<HelperOp>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct HelperOpResult {
+    pub op: HelperOp,
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verification: Option<FileVerification>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub(crate) struct HelperResult {
+    pub results: Vec<HelperOpResult>,
+}
+
+impl HelperResult {
+    // Describes every failed operation, if any
+    pub fn failures(&self) -> Option<String> {
+        let failed: Vec<String> = self
+            .results
+            .iter()
+            .filter(|r| !r.ok)
+            .map(|r| {
+                let path = match &r.op {
+                    HelperOp::Delete { path } => path,
+                    HelperOp::Copy { destination, .. } | HelperOp::Verify { destination, .. } => destination,
+                };
+                format!("{}: {}", path.display(), r.error.as_deref().unwrap_or("failed"))
+            })
+            .collect();
+        if failed.is_empty() { None } else { Some(failed.join("; ")) }
+    }
+}
+
+fn run_op(op: &HelperOp) -> Result<Option<FileVerification>, String> {
+    match op {
+        HelperOp::Delete { path } => {
+            if path.exists() {
+                fs::remove_file(path).map_err(|e| format!("delete failed: {e}"))?;
+            }
+            Ok(None)
+        }
+        HelperOp::Copy { source, destination } => {
+            if let Some(parent) = destination.parent() {
+                ensure_dir(parent).map_err(|e| format!("create directory failed: {e}"))?;
+            }
+            fs::copy(source, destination).map_err(|e| format!("copy failed: {e}"))?;
+            Ok(None)
+        }
+        HelperOp::Verify { source, destination } => {
+            let verification = verify::verify_file(source, destination, false)?;
+            match verification.status {
+                VerificationStatus::Verified => Ok(Some(verification)),
+                VerificationStatus::Missing => Err("missing after copy".to_string()),
+                _ => Err("content differs from source".to_string()),
+            }
+        }
+    }
+}
+
+// Runs every operation; a failure is recorded and the batch carries on
+pub(crate) fn execute(batch: &HelperBatch) -> HelperResult {
+    let results = batch
+        .operations
+        .iter()
+        .map(|op| match run_op(op) {
+            Ok(verification) => HelperOpResult { op: op.clone(), ok: true, error: None, verification },
+            Err(e) => HelperOpResult { op: op.clone(), ok: false, error: Some(e), verification: None },
+        })
+        .collect();
+    HelperResult { results }
+}
+
+// Handles `--elevated-helper <batch> <result>` and returns the exit code, or None
+// when the binary was started normally
+pub(crate) fn run_from_args(args: &[String]) -> Option<i32> {
+    if args.get(1).map(String::as_str) != Some(HELPER_FLAG) {
+        return None;
+    }
+    let (Some(batch_path), Some(result_path)) = (args.get(2), args.get(3)) else {
+        eprintln!("usage: {} <batch.json> <result.json>", HELPER_FLAG);
+        return Some(2);
+    };
+    let Some(batch) = read_json_file::<HelperBatch>(Path::new(batch_path)) else {
+        eprintln!("Invalid helper batch: {}", batch_path);
+        return Some(2);
+    };
+    let result = execute(&batch);
+    if let Err(e) = write_json_file(Path::new(result_path), &result) {
+        eprintln!("Failed to write helper result: {e}");
+        return Some(2);
+    }
+    Some(if result.failures().is_none() { 0 } else { 1 })
+}
+
+// Runs the batch in an elevated copy of this binary (UAC prompt) and reads back its results
+pub(crate) async fn run_elevated(app_handle: &tauri::AppHandle, batch: &HelperBatch) -> Result<HelperResult, String> {
+    let exe = std::env::current_exe().map_err(|e| format!("Failed to locate installer executable: {e}"))?;
+    let dir = brtx_dir().join("helper");
+    ensure_dir(&dir).map_err(|e| format!("Failed to create helper dir: {e}"))?;
+    // Jobs on different installations can start elevated batches at the same time
+    let stamp = unique_name("helper");
+    let batch_path = dir.join(format!("{}-batch.json", stamp));
+    let result_path = dir.join(format!("{}-result.json", stamp));
+    write_json_file(&batch_path, batch)?;
+
+    // Start-Process joins -ArgumentList with spaces, so each path is wrapped in double quotes
+    let script = Script::new()
+        .start_process(
+            Command::new("Start-Process")
+                .param("-FilePath")
+                .path(&exe)
+                .param("-Verb")
+                .literal("RunAs")
+                .param("-ArgumentList")
+                .literals([
+                    HELPER_FLAG.to_string(),
+                    format!("\"{}\"", batch_path.display()),
+                    format!("\"{}\"", result_path.display()),
+                ]),
+        )
+        .raw("exit $p.ExitCode");
+    let output = powershell::run(app_handle, &script).await;
+
+    let result = read_json_file::<HelperResult>(&result_path);
+    let _ = fs::remove_file(&batch_path);
+    let _ = fs::remove_file(&result_path);
+    match (result, output) {
+        (Some(result), _) => Ok(result),
+        (None, Ok(output)) => Err(format!(
+            "Elevated helper did not report results (UAC declined?): {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )),
+        (None, Err(e)) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("brtx-helper-test-{}", std::process::id())).join(name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn batch_reports_each_operation() {
+        let dir = scratch_dir("batch");
+        let source = dir.join("RTXStub.material.bin");
+        fs::write(&source, "stub").unwrap();
+        let old = dir.join("old.bin");
+        fs::write(&old, "old").unwrap();
+        let destination = dir.join("materials").join("RTXStub.material.bin");
+        let batch = HelperBatch {
+            operations: vec![
+                HelperOp::Delete { path: old.clone() },
+                HelperOp::Copy { source: source.clone(), destination: destination.clone() },
+                HelperOp::Verify { source: source.clone(), destination: destination.clone() },
+                HelperOp::Copy { source: dir.join("absent.bin"), destination: dir.join("absent-copy.bin") },
+            ],
+        };
+
+        let result = execute(&batch);
+
+        let ok: Vec<bool> = result.results.iter().map(|r| r.ok).collect();
+        assert_eq!(ok, vec![true, true, true, false]);
+        assert!(!old.exists());
+        assert_eq!(fs::read_to_string(&destination).unwrap(), "stub");
+        assert!(result.results[2].verification.is_some());
+        assert!(result.failures().unwrap().contains("absent-copy.bin"));
+    }
+
+    #[test]
+    fn helper_mode_round_trips_through_files() {
+        let dir = scratch_dir("files");
+        let target = dir.join("nvngx_dlss.dll");
+        fs::write(&target, "dll").unwrap();
+        let batch_path = dir.join("batch.json");
+        let result_path = dir.join("result.json");
+        write_json_file(&batch_path, &HelperBatch { operations: vec![HelperOp::Delete { path: target.clone() }] }).unwrap();
+
+        let args: Vec<String> = vec![
+            "brtx-installer".into(),
+            HELPER_FLAG.into(),
+            batch_path.to_string_lossy().into(),
+            result_path.to_string_lossy().into(),
+        ];
+        assert_eq!(run_from_args(&args), Some(0));
+        assert!(!target.exists());
+        let result: HelperResult = read_json_file(&result_path).unwrap();
+        assert!(result.failures().is_none());
+        assert_eq!(run_from_args(&["brtx-installer".to_string()]), None);
+    }
+}
@@ -0,0 +1,110 @@
+// Shared HTTP client configured from the installer settings (proxy, extra CAs)
+use reqwest::{Certificate, Client, NoProxy, Proxy};
+use std::sync::Mutex;
+
+use crate::settings::{load_settings, InstallerSettings, ProxyMode};
+
+static HTTP_CLIENT: Mutex<Option<Client>> = Mutex::new(None);
+
+// Returns the shared client, building it from the current settings on first use
+pub(crate) fn http_client() -> Result<Client, String> {
+    let mut cached = HTTP_CLIENT.lock().map_err(|e| e.to_string())?;
+    if let Some(client) = cached.as_ref() {
+        return Ok(client.clone());
+    }
+    let client = build_client(&load_settings())?;
+    *cached = Some(client.clone());
+    Ok(client)
+}
+
+// Drops the shared client so the next request picks up changed settings
+pub(crate) fn reset_http_client() {
+    if let Ok(mut cached) = HTTP_CLIENT.lock() {
+        *cached = None;
+    }
+}
+
+pub(crate) fn build_client(settings: &InstallerSettings) -> Result<Client, String> {
+    let mut builder = Client::builder();
+
+    let proxy = &settings.proxy;
+    match proxy.mode {
+        // reqwest reads the standard proxy environment variables by default
+        ProxyMode::System => {}
+        ProxyMode::None => builder = builder.no_proxy(),
+        ProxyMode::Manual => {
+            let no_proxy = proxy.no_proxy.as_deref().and_then(NoProxy::from_string);
+            let configured = [
+                ("http", proxy.http.as_deref()),
+                ("https", proxy.https.as_deref()),
+                ("all", proxy.all.as_deref()),
+            ];
+            let mut any = false;
+            for (scheme, url) in configured {
+                let Some(url) = url.map(str::trim).filter(|u| !u.is_empty()) else { continue };
+                let p = match scheme {
+                    "http" => Proxy::http(url),
+                    "https" => Proxy::https(url),
+                    _ => Proxy::all(url),
+                };
+                let mut p = p.map_err(|e| format!("Invalid proxy URL {}: {}", url, e))?;
+                if let Some(user) = proxy.username.as_deref() {
+                    p = p.basic_auth(user, proxy.password.as_deref().unwrap_or(""));
+                }
+                builder = builder.proxy(p.no_proxy(no_proxy.clone()));
+                any = true;
+            }
+            if !any {
+                return Err("Manual proxy mode requires at least one proxy URL".into());
+            }
+        }
+    }
+
+    for entry in &settings.extra_ca_certificates {
+        let pem = if entry.contains("-----BEGIN") {
+            entry.as_bytes().to_vec()
+        } else {
+            std::fs::read(entry).map_err(|e| format!("Failed to read CA certificate {}: {}", entry, e))?
+        };
+        let certs = Certificate::from_pem_bundle(&pem).map_err(|e| format!("Invalid PEM certificate {}: {}", entry, e))?;
+        if certs.is_empty() {
+            return Err(format!("No certificates found in {}", entry));
+        }
+        for cert in certs {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+
+    builder.build().map_err(|e| format!("Failed to build HTTP client: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manual(http: Option<&str>) -> InstallerSettings {
+        let mut settings = InstallerSettings::default();
+        settings.proxy.mode = ProxyMode::Manual;
+        settings.proxy.http = http.map(String::from);
+        settings
+    }
+
+    #[test]
+    fn manual_proxy_needs_a_valid_url() {
+        assert!(build_client(&manual(Some("http://proxy.corp:8080"))).is_ok());
+        assert!(build_client(&manual(None)).unwrap_err().contains("at least one proxy URL"));
+        assert!(build_client(&manual(Some("  "))).unwrap_err().contains("at least one proxy URL"));
+        assert!(build_client(&manual(Some("::not a url"))).unwrap_err().contains("Invalid proxy URL"));
+    }
+
+    #[test]
+    fn rejects_bad_certificates() {
+        let mut settings = InstallerSettings {
+            extra_ca_certificates: vec!["-----BEGIN CERTIFICATE-----\nnot base64\n-----END CERTIFICATE-----".into()],
+            ..Default::default()
+        };
+        assert!(build_client(&settings).unwrap_err().contains("Invalid PEM certificate"));
+        settings.extra_ca_certificates = vec!["/nonexistent/brtx-ca.pem".into()];
+        assert!(build_client(&settings).unwrap_err().contains("Failed to read CA certificate"));
+    }
+}
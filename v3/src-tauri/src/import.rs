@@ -0,0 +1,184 @@
+// Material sources other than a plain list of files: .rtpack, .mcpack and .zip
+// archives, and folders. The container is recognised by its content, not its extension.
+// Materials come from the pack manifest when there is one, otherwise from the
+// renderer/materials folder of a resource pack, otherwise from anywhere in the source.
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::archive;
+use crate::material;
+use crate::rtpack::{self, RtpackManifest, MANIFEST_FILE};
+use crate::settings::TrustedPublisher;
+use crate::signing::{self, SignatureStatus, SIGNATURE_FILE};
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum SourceKind {
+    // Any zip-based archive: .rtpack, .mcpack or .zip
+    Archive,
+    Folder,
+}
+
+// Zip local file header, and the end-of-directory record an empty zip starts with
+const ZIP_MAGIC: [&[u8; 4]; 2] = [b"PK\x03\x04", b"PK\x05\x06"];
+
+pub(crate) fn detect(path: &Path) -> Result<SourceKind, String> {
+    if path.is_dir() {
+        return Ok(SourceKind::Folder);
+    }
+    let mut magic = [0u8; 4];
+    File::open(path)
+        .and_then(|mut f| f.read_exact(&mut magic))
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    if ZIP_MAGIC.iter().any(|m| **m == magic) {
+        Ok(SourceKind::Archive)
+    } else {
+        Err(format!("{} is not a zip archive or a folder", path.display()))
+    }
+}
+
+// True for files directly inside a renderer/materials folder
+fn in_materials_dir(path: &Path) -> bool {
+    let parent = path.parent();
+    let named = |p: Option<&Path>, name: &str| p.and_then(|p| p.file_name()).is_some_and(|n| n.eq_ignore_ascii_case(name));
+    named(parent, "materials") && named(parent.and_then(|p| p.parent()), "renderer")
+}
+
+// Picks the material files to install among `paths`: the ones in renderer/materials
+// when there are any, otherwise all of them. Each file name may only appear once.
+pub(crate) fn select_materials(paths: Vec<PathBuf>) -> Result<Vec<PathBuf>, String> {
+    let materials: Vec<PathBuf> = paths.into_iter().filter(|p| rtpack::is_material(p)).collect();
+    let selected: Vec<PathBuf> = if materials.iter().any(|p| in_materials_dir(p)) {
+        materials.into_iter().filter(|p| in_materials_dir(p)).collect()
+    } else {
+        materials
+    };
+    for (i, path) in selected.iter().enumerate() {
+        if selected[..i].iter().any(|other| other.file_name() == path.file_name()) {
+            return Err(format!("Source contains more than one {}", path.file_name().unwrap_or_default().to_string_lossy()));
+        }
+    }
+    Ok(selected)
+}
+
+// Every file below `root`, relative to it. Symbolic links are not followed.
+fn folder_files(root: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut files = Vec::new();
+    for entry in WalkDir::new(root).into_iter().filter_map(Result::ok).filter(|e| e.file_type().is_file()) {
+        files.push(entry.path().strip_prefix(root).map_err(|e| e.to_string())?.to_path_buf());
+        if files.len() > archive::MAX_ENTRIES {
+            return Err(format!("{} has more than {} files", root.display(), archive::MAX_ENTRIES));
+        }
+    }
+    Ok(files)
+}
+
+// What a source would install, read without extracting anything
+#[derive(Debug)]
+pub(crate) struct SourceContents {
+    pub kind: SourceKind,
+    pub manifest: Option<RtpackManifest>,
+    pub signature: SignatureStatus,
+    // Relative to the source; empty when the manifest decides what is installed
+    pub materials: Vec<PathBuf>,
+}
+
+impl SourceContents {
+    // (installed file name, path in the source) of each file, from the manifest when there is one
+    pub fn installed_files(&self) -> Vec<(String, PathBuf)> {
+        let pairs: Vec<(PathBuf, PathBuf)> = match &self.manifest {
+            Some(manifest) => manifest.files.iter().map(|f| (PathBuf::from(&f.destination), PathBuf::from(&f.path))).collect(),
+            None => self.materials.iter().map(|m| (m.clone(), m.clone())).collect(),
+        };
+        pairs
+            .into_iter()
+            .filter_map(|(dest, path)| dest.file_name().map(|n| (n.to_string_lossy().to_string(), path)))
+            .collect()
+    }
+
+    // File names of the materials for planning
+    pub fn material_names(&self) -> Vec<String> {
+        self.installed_files().into_iter().map(|(name, _)| name).collect()
+    }
+
+    // Material version of each installed file whose header parses, for planning
+    pub fn material_versions(&self, source: &Path) -> Result<HashMap<String, u64>, String> {
+        let files: Vec<(String, PathBuf)> = self.installed_files().into_iter().filter(|(name, _)| rtpack::is_material(Path::new(name))).collect();
+        let headers = match self.kind {
+            SourceKind::Archive => rtpack::read_material_headers(source, &files.iter().map(|(_, p)| p.clone()).collect::<Vec<_>>())?,
+            SourceKind::Folder => files.iter().map(|(_, p)| material::parse_file_header(&source.join(p))).collect(),
+        };
+        Ok(files.into_iter().zip(headers).filter_map(|((name, _), header)| Some((name, header.ok()?.version))).collect())
+    }
+}
+
+fn read_optional(path: &Path) -> Result<Option<Vec<u8>>, String> {
+    match fs::read(path) {
+        Ok(data) => Ok(Some(data)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+pub(crate) fn survey(path: &Path, trusted: &[TrustedPublisher]) -> Result<SourceContents, String> {
+    let kind = detect(path)?;
+    let (manifest, signature, files) = match kind {
+        SourceKind::Archive => (
+            rtpack::read_manifest(path)?,
+            rtpack::read_signature(path, trusted)?,
+            rtpack::material_entries(path)?,
+        ),
+        SourceKind::Folder => {
+            let manifest_data = read_optional(&path.join(MANIFEST_FILE))?;
+            let signature_data = read_optional(&path.join(SIGNATURE_FILE))?;
+            let signature = signing::verify(manifest_data.as_deref(), signature_data.as_deref(), trusted)?;
+            let manifest = manifest_data.as_deref().map(rtpack::parse_manifest).transpose()?.flatten();
+            (manifest, signature, folder_files(path)?)
+        }
+    };
+    let materials = if manifest.is_some() { Vec::new() } else { select_materials(files)? };
+    if manifest.is_none() && materials.is_empty() {
+        return Err(format!("No materials found in {}", path.display()));
+    }
+    Ok(SourceContents { kind, manifest, signature, materials })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefers_renderer_materials() {
+        let paths = vec![
+            PathBuf::from("subpacks/low/renderer/materials/RTXStub.material.bin"),
+            PathBuf::from("renderer/materials/RTXStub.material.bin"),
+            PathBuf::from("renderer/materials/RTXPostFX.Bloom.material.bin"),
+            PathBuf::from("extras/RTXStub.material.bin"),
+            PathBuf::from("pack_icon.png"),
+        ];
+        assert!(select_materials(paths.clone()).unwrap_err().contains("more than one RTXStub.material.bin"));
+        let selected = select_materials(paths[1..].to_vec()).unwrap();
+        assert_eq!(selected, paths[1..3].to_vec());
+        assert_eq!(select_materials(vec![PathBuf::from("RTXStub.material.bin")]).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn detects_sources_by_content() {
+        let dir = std::env::temp_dir().join(format!("brtx-import-test-{}", std::process::id()));
+        let materials = dir.join("pack").join("renderer").join("materials");
+        fs::create_dir_all(&materials).unwrap();
+        fs::write(materials.join("RTXStub.material.bin"), "stub").unwrap();
+        fs::write(dir.join("pack").join(MANIFEST_FILE), r#"{"format_version":2,"header":{"name":"Pack"}}"#).unwrap();
+        fs::write(dir.join("renamed.mcpack"), "not a zip").unwrap();
+
+        let contents = survey(&dir.join("pack"), &[]).unwrap();
+        assert_eq!(contents.kind, SourceKind::Folder);
+        assert!(contents.manifest.is_none());
+        assert_eq!(contents.material_names(), vec!["RTXStub.material.bin"]);
+        assert!(detect(&dir.join("renamed.mcpack")).unwrap_err().contains("not a zip archive"));
+    }
+}
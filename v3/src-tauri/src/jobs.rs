@@ -0,0 +1,340 @@
+// Job queue for commands that write into installations. Jobs touching the same
+// installation run one after another; jobs on different installations run in parallel.
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+use tauri::Emitter;
+use tokio::sync::oneshot;
+
+use crate::operations::{self, Operation};
+use crate::results::CommandReport;
+
+// Finished jobs kept for get_job_history
+const HISTORY_LIMIT: usize = 100;
+
+static JOBS: LazyLock<Mutex<JobRegistry>> = LazyLock::new(|| Mutex::new(JobRegistry::default()));
+static INSTALL_LOCKS: LazyLock<Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+static NEXT_JOB: AtomicU64 = AtomicU64::new(1);
+
+// The work a job performs; each variant mirrors the command of the same name
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case", rename_all_fields = "camelCase")]
+pub(crate) enum JobRequest {
+    InstallPreset { uuid: String, selected_names: Vec<String>, operation_id: Option<String> },
+    InstallRtpack { rtpack_path: String, selected_names: Vec<String> },
+    ImportMaterialSource { source_path: String, selected_names: Vec<String> },
+    InstallMaterials { material_paths: Vec<String>, selected_names: Vec<String> },
+    InstallCreatorSettings {
+        settings_hash: String,
+        selected_names: Vec<String>,
+        preset_name: Option<String>,
+        uuid: Option<String>,
+        material_hashes: Option<HashMap<String, String>>,
+    },
+    InstallUploadedMaterials { selected_names: Vec<String>, preset_name: Option<String> },
+    Uninstall { selected_names: Vec<String> },
+    Undo { install_location: String },
+    InstallDlss { selected_names: Vec<String>, operation_id: Option<String> },
+    UpdateOptions { selected_names: Vec<String> },
+    Backup { dest_dir: String, selected_names: Vec<String>, operation_id: Option<String> },
+    // Every installation, so nothing else writes to them while backups are restored
+    UninstallPackage { restore_initial: bool, selected_names: Vec<String>, operation_id: Option<String> },
+}
+
+impl JobRequest {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            JobRequest::InstallPreset { .. } => "install_preset",
+            JobRequest::InstallRtpack { .. } => "install_rtpack",
+            JobRequest::ImportMaterialSource { .. } => "import_material_source",
+            JobRequest::InstallMaterials { .. } => "install_materials",
+            JobRequest::InstallCreatorSettings { .. } => "install_creator_settings",
+            JobRequest::InstallUploadedMaterials { .. } => "install_uploaded_materials",
+            JobRequest::Uninstall { .. } => "uninstall",
+            JobRequest::Undo { .. } => "undo",
+            JobRequest::InstallDlss { .. } => "install_dlss",
+            JobRequest::UpdateOptions { .. } => "update_options",
+            JobRequest::Backup { .. } => "backup",
+            JobRequest::UninstallPackage { .. } => "uninstall_package",
+        }
+    }
+
+    // The installations the job locks while it runs
+    pub fn targets(&self) -> &[String] {
+        match self {
+            JobRequest::InstallPreset { selected_names, .. }
+            | JobRequest::InstallRtpack { selected_names, .. }
+            | JobRequest::ImportMaterialSource { selected_names, .. }
+            | JobRequest::InstallMaterials { selected_names, .. }
+            | JobRequest::InstallCreatorSettings { selected_names, .. }
+            | JobRequest::InstallUploadedMaterials { selected_names, .. }
+            | JobRequest::Uninstall { selected_names }
+            | JobRequest::InstallDlss { selected_names, .. }
+            | JobRequest::UpdateOptions { selected_names }
+            | JobRequest::Backup { selected_names, .. }
+            | JobRequest::UninstallPackage { selected_names, .. } => selected_names,
+            JobRequest::Undo { install_location } => std::slice::from_ref(install_location),
+        }
+    }
+
+    // The ID the UI chose to cancel the job with, if any
+    fn operation_id(&self) -> Option<String> {
+        match self {
+            JobRequest::InstallPreset { operation_id, .. }
+            | JobRequest::InstallDlss { operation_id, .. }
+            | JobRequest::Backup { operation_id, .. }
+            | JobRequest::UninstallPackage { operation_id, .. } => operation_id.clone(),
+            _ => None,
+        }
+    }
+
+    async fn execute(self, app_handle: tauri::AppHandle, op: &Operation) -> Result<CommandReport, String> {
+        match self {
+            JobRequest::InstallPreset { uuid, selected_names, .. } => {
+                crate::install_preset_job(app_handle, uuid, selected_names, op).await
+            }
+            JobRequest::InstallRtpack { rtpack_path, selected_names } => {
+                crate::install_source_job(app_handle, rtpack_path, selected_names).await
+            }
+            JobRequest::ImportMaterialSource { source_path, selected_names } => {
+                crate::install_source_job(app_handle, source_path, selected_names).await
+            }
+            JobRequest::InstallMaterials { material_paths, selected_names } => {
+                crate::install_materials_job(app_handle, material_paths, selected_names).await
+            }
+            JobRequest::InstallCreatorSettings { settings_hash, selected_names, preset_name, uuid, material_hashes } => {
+                crate::install_creator_settings_job(app_handle, settings_hash, selected_names, preset_name, uuid, material_hashes).await
+            }
+            JobRequest::InstallUploadedMaterials { selected_names, preset_name } => {
+                crate::install_uploaded_materials_job(app_handle, selected_names, preset_name).await
+            }
+            JobRequest::Uninstall { selected_names } => crate::uninstall_job(app_handle, selected_names).await,
+            JobRequest::Undo { install_location } => crate::undo_job(app_handle, install_location).await,
+            JobRequest::InstallDlss { selected_names, .. } => crate::install_dlss_job(app_handle, selected_names, op).await,
+            JobRequest::UpdateOptions { selected_names } => crate::update_options_job(app_handle, selected_names).await,
+            JobRequest::Backup { dest_dir, selected_names, .. } => crate::backup_job(app_handle, dest_dir, selected_names, op).await,
+            JobRequest::UninstallPackage { restore_initial, .. } => {
+                crate::uninstall_package_job(app_handle, restore_initial, op).await
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum JobState {
+    // Waiting for another job on one of its installations
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub(crate) struct JobInfo {
+    pub job_id: String,
+    // Pass to cancel_operation to cancel the job
+    pub operation_id: String,
+    pub kind: String,
+    pub targets: Vec<String>,
+    pub state: JobState,
+    pub queued_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub report: Option<CommandReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Default)]
+struct JobRegistry {
+    active: HashMap<String, JobInfo>,
+    history: VecDeque<JobInfo>,
+}
+
+// Applies `change` to an active job and emits "job-state" with the new snapshot
+fn update(app_handle: &tauri::AppHandle, job_id: &str, change: impl FnOnce(&mut JobInfo)) {
+    let snapshot = {
+        let Ok(mut jobs) = JOBS.lock() else { return };
+        let Some(job) = jobs.active.get_mut(job_id) else { return };
+        change(job);
+        let snapshot = job.clone();
+        if matches!(snapshot.state, JobState::Succeeded | JobState::Failed) {
+            jobs.active.remove(job_id);
+            jobs.history.push_front(snapshot.clone());
+            jobs.history.truncate(HISTORY_LIMIT);
+        }
+        snapshot
+    };
+    let _ = app_handle.emit("job-state", snapshot);
+}
+
+fn install_lock(install_location: &str) -> Arc<tokio::sync::Mutex<()>> {
+    let mut locks = INSTALL_LOCKS.lock().unwrap_or_else(|e| e.into_inner());
+    // Install locations differ only by case on Windows
+    locks.entry(install_location.to_lowercase()).or_default().clone()
+}
+
+// Waits until every target installation is free. Locks are taken in a fixed order so
+// two jobs sharing installations cannot deadlock.
+async fn lock_targets(targets: &[String]) -> Vec<tokio::sync::OwnedMutexGuard<()>> {
+    let mut keys: Vec<String> = targets.iter().map(|t| t.to_lowercase()).collect();
+    keys.sort();
+    keys.dedup();
+    let mut guards = Vec::new();
+    for key in &keys {
+        guards.push(install_lock(key).lock_owned().await);
+    }
+    guards
+}
+
+// Queues the request and returns its job ID straight away along with a receiver for
+// the result. The job's operation is registered immediately, so it can be cancelled
+// while it waits for every target installation to be free.
+pub(crate) fn enqueue(app_handle: &tauri::AppHandle, request: JobRequest) -> Result<(String, oneshot::Receiver<Result<CommandReport, String>>), String> {
+    let job_id = format!("job-{}-{}", chrono::Utc::now().timestamp_millis(), NEXT_JOB.fetch_add(1, Ordering::Relaxed));
+    // Without an ID from the UI the job ID cancels the job
+    let op = operations::start(request.operation_id().unwrap_or_else(|| job_id.clone()), request.targets())?;
+    let info = JobInfo {
+        job_id: job_id.clone(),
+        operation_id: op.id().to_string(),
+        kind: request.kind().to_string(),
+        targets: request.targets().to_vec(),
+        state: JobState::Queued,
+        queued_at: chrono::Utc::now().to_rfc3339(),
+        started_at: None,
+        finished_at: None,
+        report: None,
+        error: None,
+    };
+    if let Ok(mut jobs) = JOBS.lock() {
+        jobs.active.insert(job_id.clone(), info.clone());
+    }
+    let _ = app_handle.emit("job-state", info);
+    let _ = app_handle.emit("operation-started", op.id());
+
+    let (tx, rx) = oneshot::channel();
+    let app = app_handle.clone();
+    let id = job_id.clone();
+    tauri::async_runtime::spawn(async move {
+        let result = match op.cancellable(async { Ok(lock_targets(request.targets()).await) }).await {
+            Ok(guards) => {
+                update(&app, &id, |job| {
+                    job.state = JobState::Running;
+                    job.started_at = Some(chrono::Utc::now().to_rfc3339());
+                });
+                let result = request.execute(app.clone(), &op).await;
+                drop(guards);
+                result
+            }
+            // Cancelled while queued: no installation was touched
+            Err(_) => Ok(cancelled_report(request.targets())),
+        };
+        drop(op);
+
+        update(&app, &id, |job| {
+            job.finished_at = Some(chrono::Utc::now().to_rfc3339());
+            match &result {
+                Ok(report) => {
+                    job.state = JobState::Succeeded;
+                    job.report = Some(report.clone());
+                }
+                Err(e) => {
+                    job.state = JobState::Failed;
+                    job.error = Some(e.clone());
+                }
+            }
+        });
+        let _ = tx.send(result);
+    });
+    Ok((job_id, rx))
+}
+
+// Queues the request and waits for it to finish
+pub(crate) async fn run(app_handle: &tauri::AppHandle, request: JobRequest) -> Result<CommandReport, String> {
+    let (_, rx) = enqueue(app_handle, request)?;
+    rx.await.map_err(|_| "Job ended without a result".to_string())?
+}
+
+fn cancelled_report(targets: &[String]) -> CommandReport {
+    let mut report = CommandReport::default();
+    for target in targets {
+        report.cancelled(target.clone());
+    }
+    report
+}
+
+// Queued and running jobs, oldest first
+pub(crate) fn active() -> Vec<JobInfo> {
+    let mut jobs: Vec<JobInfo> = JOBS.lock().map(|j| j.active.values().cloned().collect()).unwrap_or_default();
+    jobs.sort_by(|a, b| a.queued_at.cmp(&b.queued_at));
+    jobs
+}
+
+pub(crate) fn status(job_id: &str) -> Option<JobInfo> {
+    let jobs = JOBS.lock().ok()?;
+    jobs.active
+        .get(job_id)
+        .or_else(|| jobs.history.iter().find(|j| j.job_id == job_id))
+        .cloned()
+}
+
+// Finished jobs, most recent first
+pub(crate) fn history(limit: usize) -> Vec<JobInfo> {
+    JOBS.lock().map(|j| j.history.iter().take(limit).cloned().collect()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn jobs_on_the_same_installation_wait_for_each_other() {
+        let first = lock_targets(&[r"C:\Jobs\Minecraft".to_string()]).await;
+        // Install locations that differ only by case share a lock
+        let targets = [r"c:\jobs\minecraft".to_string(), r"C:\Jobs\Preview".to_string()];
+        let second = lock_targets(&targets);
+        tokio::pin!(second);
+        assert!(tokio::time::timeout(Duration::from_millis(50), &mut second).await.is_err());
+        // Other installations are not held up
+        assert_eq!(lock_targets(&[r"C:\Jobs\Other".to_string()]).await.len(), 1);
+
+        drop(first);
+        assert_eq!(second.await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn queued_job_can_be_cancelled() {
+        let targets = vec![r"C:\Jobs\Queued".to_string()];
+        let running = lock_targets(&targets).await;
+        let op = operations::start("op-test-queued".to_string(), &targets).unwrap();
+
+        let cancelling = tokio::spawn(operations::cancel("op-test-queued"));
+        let waited = op.cancellable(async { Ok(lock_targets(&targets).await) }).await;
+        assert!(waited.is_err());
+        drop(op);
+        let report = serde_json::to_value(cancelling.await.unwrap().unwrap()).unwrap();
+        assert_eq!(report["untouched"][0], targets[0]);
+        drop(running);
+
+        let json = serde_json::to_value(cancelled_report(&targets)).unwrap();
+        assert_eq!(json["targets"][0]["status"], "cancelled");
+    }
+
+    #[test]
+    fn requests_round_trip_through_json() {
+        let request = JobRequest::UninstallPackage { restore_initial: true, selected_names: vec!["C:\\A".to_string()], operation_id: Some("op-1".to_string()) };
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["kind"], "uninstall_package");
+        assert_eq!(json["restoreInitial"], true);
+        let back: JobRequest = serde_json::from_value(json).unwrap();
+        assert_eq!(back.kind(), "uninstall_package");
+        assert_eq!(back.targets(), ["C:\\A"]);
+        assert_eq!(back.operation_id().as_deref(), Some("op-1"));
+    }
+}
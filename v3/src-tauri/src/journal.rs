@@ -0,0 +1,188 @@
+// Append-only journal of every change made to an installation, one JSON line per
+// entry in a file per installation. Entries are never rewritten.
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::transaction::ROLLED_BACK;
+use crate::{brtx_dir, ensure_dir, sha256_hex};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum JournalOperation {
+    Install,
+    Uninstall,
+    // Restoring the backup taken before the first install
+    Restore,
+    Dlss,
+    Options,
+    // Restoring the files and preset record from before the last change
+    Undo,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub(crate) enum JournalOutcome {
+    Succeeded,
+    Failed { reason: String },
+    // Failed after writing, and the previous files were restored
+    RolledBack { reason: String },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct JournalEntry {
+    pub timestamp: String,
+    pub install_location: String,
+    pub operation: JournalOperation,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preset_uuid: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preset_name: Option<String>,
+    // The file, folder or URL the installed files came from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    // SHA-256 of each installed file, keyed by file name
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub file_hashes: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backend: Option<String>,
+    #[serde(flatten)]
+    pub outcome: JournalOutcome,
+}
+
+impl JournalEntry {
+    pub fn new(install_location: &str, operation: JournalOperation) -> Self {
+        Self {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            install_location: install_location.to_string(),
+            operation,
+            preset_uuid: None,
+            preset_name: None,
+            source: None,
+            file_hashes: HashMap::new(),
+            backend: None,
+            outcome: JournalOutcome::Succeeded,
+        }
+    }
+
+    pub fn preset(mut self, uuid: &str, name: &str) -> Self {
+        self.preset_uuid = Some(uuid.to_string());
+        self.preset_name = Some(name.to_string());
+        self
+    }
+
+    pub fn source(mut self, source: Option<&str>) -> Self {
+        self.source = source.map(str::to_string);
+        self
+    }
+
+    pub fn files(mut self, file_hashes: HashMap<String, String>) -> Self {
+        self.file_hashes = file_hashes;
+        self
+    }
+
+    pub fn backend(mut self, backend: Option<&str>) -> Self {
+        self.backend = backend.map(str::to_string);
+        self
+    }
+
+    pub fn outcome<T>(mut self, result: &Result<T, String>) -> Self {
+        self.outcome = match result {
+            Ok(_) => JournalOutcome::Succeeded,
+            Err(e) => match e.strip_suffix(ROLLED_BACK) {
+                Some(reason) => JournalOutcome::RolledBack { reason: reason.trim_end().to_string() },
+                None => JournalOutcome::Failed { reason: e.clone() },
+            },
+        };
+        self
+    }
+}
+
+// File name stem for per-installation data; install paths contain characters that
+// are awkward in file names and differ only by case on Windows
+pub(crate) fn location_key(install_location: &str) -> String {
+    sha256_hex(install_location.to_lowercase().as_bytes())[..16].to_string()
+}
+
+fn journal_path(install_location: &str) -> PathBuf {
+    brtx_dir().join("journal").join(format!("{}.jsonl", location_key(install_location)))
+}
+
+fn append_to(path: &Path, entry: &JournalEntry) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        ensure_dir(parent).map_err(|e| format!("Failed to create journal dir: {e}"))?;
+    }
+    let mut line = serde_json::to_string(entry).map_err(|e| e.to_string())?;
+    line.push('\n');
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open journal: {e}"))?;
+    file.write_all(line.as_bytes()).map_err(|e| format!("Failed to write journal: {e}"))
+}
+
+// Entries most recent first; lines that cannot be parsed are skipped
+fn read_from(path: &Path, operation: Option<JournalOperation>, limit: usize) -> Result<Vec<JournalEntry>, String> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(path).map_err(|e| format!("Failed to read journal: {e}"))?;
+    Ok(content
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str::<JournalEntry>(line).ok())
+        .filter(|entry| operation.is_none_or(|op| entry.operation == op))
+        .take(limit)
+        .collect())
+}
+
+// Appends the entry to its installation's journal. A journal that cannot be written
+// never fails the change it describes.
+pub(crate) fn record(entry: &JournalEntry) {
+    if let Err(e) = append_to(&journal_path(&entry.install_location), entry) {
+        println!("⚠ Failed to record journal entry: {}", e);
+    }
+}
+
+pub(crate) fn query(install_location: &str, operation: Option<JournalOperation>, limit: usize) -> Result<Vec<JournalEntry>, String> {
+    read_from(&journal_path(install_location), operation, limit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_are_appended_and_read_newest_first() {
+        let dir = std::env::temp_dir().join(format!("brtx-journal-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("journal.jsonl");
+        let location = r"C:\Program Files\WindowsApps\Microsoft.MinecraftUWP";
+
+        let installed = JournalEntry::new(location, JournalOperation::Install)
+            .preset("abc", "Vanilla RTX")
+            .files(HashMap::from([("RTXStub.material.bin".to_string(), "00ff".to_string())]))
+            .backend(Some("iobit"))
+            .outcome(&Ok::<(), String>(()));
+        let failed = JournalEntry::new(location, JournalOperation::Dlss)
+            .source(Some("nvngx_dlss.dll"))
+            .outcome(&Err::<(), String>(format!("Verification failed {}", ROLLED_BACK)));
+        append_to(&path, &installed).unwrap();
+        append_to(&path, &failed).unwrap();
+
+        let entries = read_from(&path, None, 10).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].operation, JournalOperation::Dlss);
+        assert_eq!(entries[0].outcome, JournalOutcome::RolledBack { reason: "Verification failed".to_string() });
+        assert_eq!(entries[1].file_hashes["RTXStub.material.bin"], "00ff");
+
+        let installs = read_from(&path, Some(JournalOperation::Install), 10).unwrap();
+        assert_eq!(installs.len(), 1);
+        assert_eq!(installs[0].backend.as_deref(), Some("iobit"));
+        assert_eq!(read_from(&path, None, 1).unwrap().len(), 1);
+        assert!(read_from(&dir.join("absent.jsonl"), None, 10).unwrap().is_empty());
+    }
+}
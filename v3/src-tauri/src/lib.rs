@@ -1,20 +1,74 @@
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 use chrono::{Local, DateTime, Utc};
+use futures::stream::{self, StreamExt, TryStreamExt};
 use reqwest::Client;
 use std::fs::{self, File};
-use std::io::Read;
 use std::path::{Path, PathBuf};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use walkdir::WalkDir;
 use winreg::enums::*;
 use winreg::RegKey;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
 use tauri::Emitter;
 use tauri_plugin_dialog::DialogExt;
-use tauri_plugin_shell::ShellExt;
 use url::Url;
 
+mod archive;
+mod backend;
+mod diff;
+mod helper;
+mod http;
+mod import;
+mod jobs;
+mod journal;
+mod material;
+mod operations;
+mod plan;
+mod powershell;
+mod results;
+mod rtpack;
+mod settings;
+mod signing;
+mod transaction;
+mod undo;
+mod verify;
+
+use http::http_client;
+use jobs::JobRequest;
+use journal::{JournalEntry, JournalOperation};
+use operations::Operation;
+use plan::{ActionPlan, PlannedDownload, TrackingChange};
+use results::{CommandReport, InstalledFiles};
+use settings::InstallerSettings;
+use transaction::{install_files, replace_files};
+use verify::FileVerification;
+
 const BRTX_DIR_NAME: &str = "graphics.bedrock";
+// RTX material files that IObit deletes before every installation
+const RTX_MATERIAL_FILES: [&str; 3] = [
+    "RTXStub.material.bin",
+    "RTXPostFX.Tonemapping.material.bin",
+    "RTXPostFX.Bloom.material.bin",
+];
+// Upper bound on material downloads running at the same time
+const MAX_CONCURRENT_DOWNLOADS: usize = 4;
+
+// Serializes read-modify-write access to cache.json across concurrent tasks
+static CACHE_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+// Same for installed_presets.json, which jobs on different installations update concurrently
+static PRESETS_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+// Serializes pack extraction, the tracking of which extraction an installation uses,
+// and the cleanup of extraction directories nothing uses any more
+static EXTRACT_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+// Extractions that a running install is still copying from, by pack hash
+static PACKS_IN_USE: std::sync::Mutex<Vec<String>> = std::sync::Mutex::new(Vec::new());
+// Lets one job download the DLSS package while jobs on other installations wait for it
+static DLSS_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+// options.txt is shared by every installation of the same edition
+static OPTIONS_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+static NEXT_SCRATCH: AtomicU64 = AtomicU64::new(1);
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 struct Installation {
@@ -35,6 +89,12 @@ struct InstalledPreset {
     installed_at: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     is_creator: Option<bool>,
+    // SHA-256 of each installed material, keyed by file name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    material_hashes: Option<HashMap<String, String>>,
+    // SHA-256 of the .rtpack file this came from, which keeps its extraction directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rtpack_sha256: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -44,6 +104,21 @@ pub struct PackInfo {
     pub stub: String,
     pub tonemapping: String,
     pub bloom: String,
+    // Expected SHA-256 of each material, when the catalog provides them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stub_sha256: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tonemapping_sha256: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bloom_sha256: Option<String>,
+}
+
+// A single file to fetch, with the hash it must match when known
+#[derive(Debug, Clone)]
+struct MaterialDownload {
+    url: String,
+    path: PathBuf,
+    sha256: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -107,13 +182,7 @@ fn is_cache_valid<T>(cached: &CacheEntry<T>) -> bool {
 }
 
 async fn run_powershell_async(app_handle: tauri::AppHandle, script: &str) -> Result<String, String> {
-    let shell = app_handle.shell();
-    let output = shell
-        .command("powershell.exe")
-        .args(["-NoProfile", "-ExecutionPolicy", "Bypass", "-Command", script])
-        .output()
-        .await
-        .map_err(|e| format!("Failed to spawn PowerShell: {e}"))?;
+    let output = powershell::run(&app_handle, &powershell::Script::new().raw(script)).await?;
 
     if output.status.success() {
         Ok(String::from_utf8_lossy(&output.stdout).to_string())
@@ -174,12 +243,10 @@ fn get_iobit_unlocker_exe() -> Option<PathBuf> {
     for root in [r"C:\\Program Files", r"C:\\Program Files (x86)"] {
         let rootp = Path::new(root);
         if rootp.exists() {
-            for entry in WalkDir::new(rootp).max_depth(3) {
-                if let Ok(e) = entry {
-                    let p = e.path();
-                    if p.file_name().map(|n| n.to_string_lossy().eq_ignore_ascii_case("IObitUnlocker.exe")).unwrap_or(false) {
-                        return Some(p.to_path_buf());
-                    }
+            for e in WalkDir::new(rootp).max_depth(3).into_iter().flatten() {
+                let p = e.path();
+                if p.file_name().map(|n| n.to_string_lossy().eq_ignore_ascii_case("IObitUnlocker.exe")).unwrap_or(false) {
+                    return Some(p.to_path_buf());
                 }
             }
         }
@@ -187,291 +254,99 @@ fn get_iobit_unlocker_exe() -> Option<PathBuf> {
     None
 }
 
-async fn iobit_delete_async(app_handle: &tauri::AppHandle, iobit: &Path, location: &Path, materials: &[PathBuf]) -> Result<(), String> {
-    // RTX material files that need to be deleted before installation
-    let rtx_files_to_delete = [
-        "RTXStub.material.bin",
-        "RTXPostFX.Tonemapping.material.bin", 
-        "RTXPostFX.Bloom.material.bin"
-    ];
-    
-    // Collect all files to delete (RTX files + materials), avoiding duplicates
-    let mut files_to_delete = std::collections::HashSet::new();
-    
-    // Add hardcoded RTX files
-    for file_name in &rtx_files_to_delete {
-        files_to_delete.insert(file_name.to_string());
-    }
-    
-    // Add material files being installed
-    for m in materials {
-        if let Some(name) = m.file_name() {
-            if let Some(name_str) = name.to_str() {
-                files_to_delete.insert(name_str.to_string());
-            }
-        }
-    }
-    
-    if files_to_delete.is_empty() {
-        println!("No files to delete");
-        return Ok(());
-    }
-
-    // Build target paths for all files to delete
-    let targets: Vec<_> = files_to_delete
-        .iter()
-        .map(|file_name| location.join("data").join("renderer").join("materials").join(file_name))
-        .collect();
-
-    // Build the exact ArgumentList string for single-pass delete:
-    // '/Delete "target1","target2","target3"'
-    let targets_joined = targets
+async fn copy_shader_files_async(app_handle: &tauri::AppHandle, install_location: &str, materials: &[PathBuf], pack: &PackInfo, operation: JournalOperation, source: Option<&str>, known_hashes: &DownloadHashes) -> Result<InstalledFiles, String> {
+    let mc_dest = Path::new(install_location).join("data").join("renderer").join("materials");
+    let pairs = materials
         .iter()
-        .map(|t| t.display().to_string())
-        .collect::<Vec<_>>()
-        .join("\",\"");
-    let arglist = format!("/Delete \"{}\"", targets_joined);
-
-    // Escape single quotes for embedding in a single-quoted PS string
-    let ioexe_ps = iobit.display().to_string().replace("'", "''");
-    let arglist_ps = arglist.replace("'", "''");
-
-    let ps_cmd = format!(
-        "Start-Process -FilePath '{}' -ArgumentList '{}' -Wait -PassThru",
-        ioexe_ps, arglist_ps
-    );
-
-    println!(
-        "Deleting materials via IObit (single pass): [{}]",
-        files_to_delete.iter().cloned().collect::<Vec<_>>().join(", ")
-    );
-
-    let shell = app_handle.shell();
-    let output = shell
-        .command("powershell.exe")
-        .args(["-NoProfile", "-ExecutionPolicy", "Bypass", "-Command", &ps_cmd])
-        .output()
-        .await
-        .map_err(|e| format!("Failed to run PowerShell for IObit delete: {e}"))?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        println!("IObit delete reported non-success: {}", stderr);
-    }
-
-    println!("IObit single-pass delete completed");
-    Ok(())
-}
-
-async fn iobit_copy_async(app_handle: &tauri::AppHandle, iobit: &Path, destination: &Path, materials: &[PathBuf]) -> Result<(), String> {
-    // Best-effort ensure destination directory for sideloaded installs
-    let dest_dir = destination.join("data").join("renderer").join("materials");
-    let _ = ensure_dir(&dest_dir);
-
-    if materials.is_empty() {
-        println!("No materials to copy");
-        return Ok(());
+        .map(|m| Ok((m.clone(), mc_dest.join(m.file_name().ok_or_else(|| format!("Invalid material filename: {}", m.display()))?))))
+        .collect::<Result<Vec<_>, String>>()?;
+    install_pack_files(app_handle, install_location, &pairs, pack, operation, source, known_hashes).await
+}
+
+// Installs (source, destination) pairs into one installation and records the pack as
+// its installed preset, with a journal entry and an undo point. `known_hashes` saves
+// hashing files again that were verified while downloading.
+async fn install_pack_files(app_handle: &tauri::AppHandle, install_location: &str, pairs: &[(PathBuf, PathBuf)], pack: &PackInfo, operation: JournalOperation, source: Option<&str>, known_hashes: &DownloadHashes) -> Result<InstalledFiles, String> {
+    let materials: Vec<PathBuf> = pairs.iter().map(|(src, _)| src.clone()).collect();
+    let target_dir = pairs
+        .first()
+        .and_then(|(_, dest)| dest.parent())
+        .ok_or("No files to install")?;
+    // Refuse files that are not materials, such as an error page saved by a failed download
+    let mut warnings = Vec::new();
+    for (src, dest) in pairs.iter().filter(|(_, dest)| rtpack::is_material(dest)) {
+        let info = material::parse_file(src)?;
+        let file_name = dest.file_name().unwrap_or_default().to_string_lossy();
+        warnings.extend(material::version_warning(&file_name, info.version, dest));
     }
-
-    // Build the exact ArgumentList string used in PowerShell v2 implementation:
-    // '/Copy "src1","src2" "destDir"'
-    let sources_joined = materials
-        .iter()
-        .map(|m| m.display().to_string())
-        .collect::<Vec<_>>()
-        .join("\",\"");
-    let arglist = format!("/Copy \"{}\" \"{}\"", sources_joined, dest_dir.display());
-
-    // Escape single quotes for embedding in a single-quoted PS string
-    let ioexe_ps = iobit.display().to_string().replace("'", "''");
-    let arglist_ps = arglist.replace("'", "''");
-
-    let ps_cmd = format!(
-        "Start-Process -FilePath '{}' -ArgumentList '{}' -Wait -PassThru",
-        ioexe_ps, arglist_ps
+    let hashes = hash_material_files_with(&materials, known_hashes);
+    let previous_preset = get_installed_preset(install_location);
+    // Each backend attempt is a transaction that restores the previous files if it fails
+    let attempt = install_files(app_handle, target_dir, pairs).await;
+    journal::record(
+        &JournalEntry::new(install_location, operation)
+            .preset(&pack.uuid, &pack.name)
+            .source(source)
+            .files(hashes.clone())
+            .backend(attempt.backend)
+            .outcome(&attempt.result),
     );
-
-    println!(
-        "Copying materials via IObit (single pass): [{}] -> {}",
-        materials
-            .iter()
-            .map(|m| m.file_name().unwrap_or_default().to_string_lossy().to_string())
-            .collect::<Vec<_>>()
-            .join(", "),
-        dest_dir.display()
-    );
-
-    let shell = app_handle.shell();
-    let output = shell
-        .command("powershell.exe")
-        .args(["-NoProfile", "-ExecutionPolicy", "Bypass", "-Command", &ps_cmd])
-        .output()
-        .await
-        .map_err(|e| format!("Failed to run PowerShell for IObit copy: {e}"))?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("IObit copy via PowerShell failed: {}", stderr));
+    let verification = attempt.result?;
+    if let Some(snapshot) = attempt.snapshot {
+        undo::keep(install_location, operation, snapshot, previous_preset);
     }
+    finish_install(app_handle, install_location, pack, hashes, &verification);
+    Ok(InstalledFiles { files: verification, warnings })
+}
 
-    println!("IObit single-pass copy completed");
-    Ok(())
+#[derive(Serialize, Clone)]
+struct InstallVerificationEvent<'a> {
+    install_location: &'a str,
+    files: &'a [FileVerification],
 }
 
-async fn copy_shader_files_async(app_handle: &tauri::AppHandle, install_location: &str, materials: &[PathBuf], pack: &PackInfo) -> Result<(), String> {
-    let mc_dest = Path::new(install_location).join("data").join("renderer").join("materials");
-    let sideloaded = is_sideloaded(install_location);
-
-    // For WindowsApps installs, prefer IObit Unlocker; for sideloaded, use direct copy
-    // Only use IObit for WindowsApps (non-sideloaded) installations
-
-    if sideloaded {
-        // Fallback for sideloaded installations: ensure dir, delete then copy directly
-        ensure_dir(&mc_dest).map_err(|e| format!("Failed to create materials dir: {e}"))?;
-        for m in materials {
-            if !m.exists() { return Err(format!("Source material not found: {}", m.display())); }
-            let dest = mc_dest.join(m.file_name().ok_or("invalid material filename")?);
-            if dest.exists() {
-                println!("Removing existing file before copy: {}", dest.display());
-                let _ = fs::remove_file(&dest);
-            }
-            fs::copy(m, &dest).map_err(|e| format!("Direct copy failed to {}: {e}", dest.display()))?;
-        }
-        let installed_preset = InstalledPreset {
-            uuid: pack.uuid.clone(),
-            name: pack.name.clone(),
-            installed_at: chrono::Utc::now().to_rfc3339(),
-            is_creator: None,
-        };
-        if let Err(e) = save_installed_preset(install_location, &installed_preset) {
-            println!("⚠ Failed to save preset tracking: {}", e);
-        }
-        return Ok(());
-    }
-
-    if let Some(ioexe) = get_iobit_path_cached() {
-        println!("Using IObit Unlocker for WindowsApps delete+copy");
-        match try_iobit_copy_async(app_handle, &ioexe, install_location, materials).await {
-            Ok(_) => {
-                let installed_preset = InstalledPreset {
-                    uuid: pack.uuid.clone(),
-                    name: pack.name.clone(),
-                    installed_at: chrono::Utc::now().to_rfc3339(),
-                    is_creator: None,
-                };
-                if let Err(e) = save_installed_preset(install_location, &installed_preset) {
-                    println!("⚠ Failed to save preset tracking: {}", e);
-                }
-                return Ok(());
-            }
-            Err(e) => {
-                println!("⚠ IObit Unlocker failed: {}", e);
-                // Continue to appropriate fallback below
-            }
-        }
-    } else {
-        println!("⚠ IObit Unlocker not found, using fallback method for WindowsApps");
-    }
+fn finish_install(app_handle: &tauri::AppHandle, install_location: &str, pack: &PackInfo, hashes: HashMap<String, String>, verification: &[FileVerification]) {
+    track_installed_pack(install_location, pack, hashes);
+    let _ = app_handle.emit("install-verification", InstallVerificationEvent { install_location, files: verification });
+}
 
-    println!("Attempting elevated PowerShell fallback...");
-    match try_elevated_copy_async(app_handle, &mc_dest, materials).await {
-        Ok(_) => {
-            let installed_preset = InstalledPreset {
-                uuid: pack.uuid.clone(),
-                name: pack.name.clone(),
-                installed_at: chrono::Utc::now().to_rfc3339(),
-                is_creator: None,
-            };
-            if let Err(e) = save_installed_preset(install_location, &installed_preset) {
-                println!("⚠ Failed to save preset tracking: {}", e);
-            }
-            Ok(())
-        }
-        Err(e) => Err(e)
+fn track_installed_pack(install_location: &str, pack: &PackInfo, hashes: HashMap<String, String>) {
+    let installed_preset = InstalledPreset {
+        uuid: pack.uuid.clone(),
+        name: pack.name.clone(),
+        installed_at: chrono::Utc::now().to_rfc3339(),
+        is_creator: None,
+        material_hashes: Some(hashes),
+        rtpack_sha256: None,
+    };
+    if let Err(e) = save_installed_preset(install_location, &installed_preset) {
+        println!("⚠ Failed to save preset tracking: {}", e);
     }
 }
 
-async fn try_iobit_copy_async(app_handle: &tauri::AppHandle, ioexe: &Path, install_location: &str, materials: &[PathBuf]) -> Result<(), String> {
-    println!("Starting IObit operations for {} files", materials.len());
-    let install_path = Path::new(install_location);
-    
-    // Delete existing files first
-    println!("Deleting existing material files...");
-    iobit_delete_async(app_handle, ioexe, install_path, materials).await?;
-    
-    // Copy new files
-    println!("Copying material files with IObit Unlocker...");
-    iobit_copy_async(app_handle, ioexe, install_path, materials).await?;
-    
-    // Skipping file verification per user preference
-    std::thread::sleep(std::time::Duration::from_millis(500));
-    
-    println!("IObit operations completed successfully");
-    Ok(())
+fn sha256_hex(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
 }
 
-async fn try_elevated_copy_async(app_handle: &tauri::AppHandle, mc_dest: &Path, materials: &[PathBuf]) -> Result<(), String> {
-    // Use PowerShell with elevation request to copy files
-    let mut ps_script = String::from("Start-Process powershell -Verb RunAs -ArgumentList '-Command', '");
-    
-    for m in materials {
-        let src = m.to_string_lossy().replace('\\', "\\\\").replace('\'', "\'\'");
-        let dest_file = mc_dest.join(m.file_name().unwrap());
-        let dest = dest_file.to_string_lossy().replace('\\', "\\\\").replace('\'', "\'\'");
-        ps_script.push_str(&format!("Copy-Item '{}' '{}' -Force; ", src, dest));
-    }
-    
-    ps_script.push_str("' -Wait");
-    
-    let shell = app_handle.shell();
-    let output = shell
-        .command("powershell.exe")
-        .args(["-NoProfile", "-ExecutionPolicy", "Bypass", "-Command", &ps_script])
-        .output()
-        .await
-        .map_err(|e| format!("Failed to run elevated PowerShell: {e}"))?;
-
-    if output.status.success() {
-        // Skipping file verification per user preference
-        Ok(())
-    } else {
-        Err(format!("Elevated PowerShell failed: {}", String::from_utf8_lossy(&output.stderr)))
-    }
-}
-
-fn extract_rtpack_to(pack: &Path, out_dir: &Path) -> Result<(), String> {
-    ensure_dir(out_dir).map_err(|e| e.to_string())?;
-    let mut f = File::open(pack).map_err(|e| format!("Open pack failed: {e}"))?;
-    let mut data = Vec::new();
-    f.read_to_end(&mut data).map_err(|e| format!("Read pack failed: {e}"))?;
-    let reader = std::io::Cursor::new(data);
-    let mut zip = zip::ZipArchive::new(reader).map_err(|e| format!("Invalid .rtpack: {e}"))?;
-    for i in 0..zip.len() {
-        let mut file = zip.by_index(i).map_err(|e| e.to_string())?;
-        let outpath = out_dir.join(file.name());
-        if file.name().ends_with('/') {
-            ensure_dir(&outpath).map_err(|e| e.to_string())?;
-        } else {
-            if let Some(parent) = outpath.parent() { ensure_dir(parent).map_err(|e| e.to_string())?; }
-            let mut outfile = File::create(&outpath).map_err(|e| e.to_string())?;
-            std::io::copy(&mut file, &mut outfile).map_err(|e| e.to_string())?;
-        }
-    }
-    Ok(())
+fn hash_material_files(materials: &[PathBuf]) -> HashMap<String, String> {
+    hash_material_files_with(materials, &DownloadHashes::new())
 }
 
-fn find_materials(root: &Path) -> Vec<PathBuf> {
-    let mut v = Vec::new();
-    for entry in WalkDir::new(root).into_iter().filter_map(Result::ok) {
-        let p = entry.path();
-        if p.is_file() && p.extension().map(|e| e == "bin").unwrap_or(false) {
-            if let Some(stem) = p.file_stem() {
-                let s = stem.to_string_lossy();
-                if s.contains("material") { v.push(p.to_path_buf()); }
-            }
-        }
+// SHA-256 of each material keyed by file name, reading only the files `known` lacks
+fn hash_material_files_with(materials: &[PathBuf], known: &DownloadHashes) -> HashMap<String, String> {
+    let mut hashes = HashMap::new();
+    for m in materials {
+        let Some(name) = m.file_name() else { continue };
+        let hash = match known.get(m) {
+            Some(hash) => hash.clone(),
+            None => match fs::read(m) {
+                Ok(data) => sha256_hex(&data),
+                Err(_) => continue,
+            },
+        };
+        hashes.insert(name.to_string_lossy().to_string(), hash);
     }
-    v
+    hashes
 }
 
 fn read_json_file<T: for<'de> Deserialize<'de>>(p: &Path) -> Option<T> {
@@ -497,7 +372,7 @@ fn find_launcher_installs(
 
     let new_installations = entries
         .filter_map(Result::ok)
-        .filter(|entry| entry.file_type().map_or(false, |ft| ft.is_dir()))
+        .filter(|entry| entry.file_type().is_ok_and(|ft| ft.is_dir()))
         .filter_map(|entry| {
             let path = entry.path();
             let version_name = path.file_name()?.to_str()?;
@@ -581,21 +456,24 @@ async fn list_presets(force_refresh: bool) -> Result<Vec<PackInfo>, String> {
     let packs_dir = brtx_dir().join("packs");
     ensure_dir(&packs_dir).map_err(|e| e.to_string())?;
     
-    let url = "https://bedrock.graphics/api";
-    let client = Client::new();
-    let text = client
-        .get(url)
-        .send().await.map_err(|e| e.to_string())?
-        .text().await.map_err(|e| e.to_string())?;
-    
-    // Parse the JSON response directly as PackInfo array
-    let presets: Vec<PackInfo> = serde_json::from_str(&text)
-        .map_err(|e| format!("Failed to parse presets JSON: {}. Response was: {}", e, text))?;
+    let presets = match fetch_presets().await {
+        Ok(presets) => presets,
+        Err(e) => {
+            // Offline: fall back to the last catalog we have, even if expired
+            if let Some(cached_presets) = cache.presets {
+                println!("⚠ Failed to fetch presets ({}); using cached catalog", e);
+                return Ok(cached_presets.data);
+            }
+            return Err(e);
+        }
+    };
     
     // Cache the results for 1 hour
     let now = Utc::now();
     let expires_at = now + chrono::Duration::hours(1);
-    // Update cache with fresh data
+    // Update cache with fresh data; reload under the lock so concurrent downloads aren't lost
+    let _guard = CACHE_LOCK.lock().await;
+    cache = load_cache().await;
     cache.presets = Some(CacheEntry {
         data: presets.clone(),
         timestamp: now,
@@ -607,6 +485,35 @@ async fn list_presets(force_refresh: bool) -> Result<Vec<PackInfo>, String> {
     Ok(presets)
 }
 
+async fn fetch_presets() -> Result<Vec<PackInfo>, String> {
+    let url = "https://bedrock.graphics/api";
+    let client = http_client()?;
+    let text = client
+        .get(url)
+        .send().await.map_err(|e| e.to_string())?
+        .text().await.map_err(|e| e.to_string())?;
+    
+    // Parse the JSON response directly as PackInfo array
+    serde_json::from_str(&text)
+        .map_err(|e| format!("Failed to parse presets JSON: {}. Response was: {}", e, text))
+}
+
+async fn catalog_for_planning() -> Result<Vec<PackInfo>, String> {
+    // A dry run never touches the network, so without a cached catalog there is nothing to plan from
+    load_cache()
+        .await
+        .presets
+        .map(|cached| cached.data)
+        .ok_or_else(|| "Preset catalog unavailable: load the presets before planning an install".to_string())
+}
+
+fn material_file_names(materials: &[PathBuf]) -> Vec<String> {
+    materials
+        .iter()
+        .filter_map(|m| m.file_name().map(|n| n.to_string_lossy().to_string()))
+        .collect()
+}
+
 fn get_installed_preset(install_location: &str) -> Option<InstalledPreset> {
     let presets_file = brtx_dir().join("installed_presets.json");
     if let Some(installations) = read_json_file::<HashMap<String, InstalledPreset>>(&presets_file) {
@@ -616,11 +523,12 @@ fn get_installed_preset(install_location: &str) -> Option<InstalledPreset> {
 }
 
 fn save_installed_preset(install_location: &str, preset: &InstalledPreset) -> Result<(), String> {
+    let _lock = PRESETS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
     let presets_file = brtx_dir().join("installed_presets.json");
     
     // Load existing installations or create new map
     let mut installations = read_json_file::<HashMap<String, InstalledPreset>>(&presets_file)
-        .unwrap_or_else(HashMap::new);
+        .unwrap_or_default();
     
     // Update with new preset
     installations.insert(install_location.to_string(), preset.clone());
@@ -629,6 +537,22 @@ fn save_installed_preset(install_location: &str, preset: &InstalledPreset) -> Re
     write_json_file(&presets_file, &installations)
 }
 
+// Pack hashes of the extraction directories installations still refer to
+fn referenced_pack_hashes() -> Vec<String> {
+    read_json_file::<HashMap<String, InstalledPreset>>(&brtx_dir().join("installed_presets.json"))
+        .map(|installations| installations.into_values().filter_map(|p| p.rtpack_sha256).collect())
+        .unwrap_or_default()
+}
+
+fn remove_installed_preset(install_location: &str) {
+    let _lock = PRESETS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let presets_file = brtx_dir().join("installed_presets.json");
+    if let Some(mut installations) = read_json_file::<HashMap<String, InstalledPreset>>(&presets_file) {
+        installations.remove(install_location);
+        let _ = write_json_file(&presets_file, &installations);
+    }
+}
+
 async fn get_cached_download(url: &str) -> Option<Vec<u8>> {
     let cache = load_cache().await;
     if let Some(cached) = cache.downloads.get(url) {
@@ -640,6 +564,7 @@ async fn get_cached_download(url: &str) -> Option<Vec<u8>> {
 }
 
 async fn cache_download(url: &str, data: &[u8]) -> Result<(), String> {
+    let _guard = CACHE_LOCK.lock().await;
     let mut cache = load_cache().await;
     let now = Utc::now();
     let expires_at = now + chrono::Duration::hours(24); // Cache downloads for 24 hours
@@ -653,17 +578,169 @@ async fn cache_download(url: &str, data: &[u8]) -> Result<(), String> {
     save_cache(&cache).await
 }
 
-// Helper: download a URL to a file path with caching
-async fn download_to_file_with_cache(client: &Client, url: &str, file_path: &Path) -> Result<(), String> {
+async fn purge_cached_download(url: &str) -> Result<(), String> {
+    let _guard = CACHE_LOCK.lock().await;
+    let mut cache = load_cache().await;
+    if cache.downloads.remove(url).is_some() {
+        save_cache(&cache).await?;
+    }
+    Ok(())
+}
+
+// A file or directory name no other task in this process is using, for scratch space
+// shared by jobs that run in parallel
+fn unique_name(prefix: &str) -> String {
+    format!("{}-{}-{}", prefix, chrono::Utc::now().format("%Y%m%d%H%M%S%f"), NEXT_SCRATCH.fetch_add(1, Ordering::Relaxed))
+}
+
+// Writes through a temporary file and renames it into place, so a job reading `path`
+// never sees a partial file while another job downloads the same preset
+async fn write_file_atomic(path: &Path, data: &[u8]) -> Result<(), String> {
+    let tmp = path.with_extension(unique_name("part"));
+    tokio::fs::write(&tmp, data).await.map_err(|e| e.to_string())?;
+    if let Err(e) = tokio::fs::rename(&tmp, path).await {
+        let _ = tokio::fs::remove_file(&tmp).await;
+        return Err(e.to_string());
+    }
+    Ok(())
+}
+
+// Helper: download a URL to a file path with caching.
+// When an expected SHA-256 is given, cached and fresh bytes must match it; a mismatch
+// purges the cache entry and is a hard error. Returns the SHA-256 of the written file.
+async fn download_to_file_with_cache(client: &Client, url: &str, file_path: &Path, expected_sha256: Option<&str>) -> Result<String, String> {
     if let Some(cached_data) = get_cached_download(url).await {
-        tokio::fs::write(file_path, cached_data).await.map_err(|e| e.to_string())?;
-        return Ok(());
+        let hash = sha256_hex(&cached_data);
+        if expected_sha256.is_none_or(|expected| expected.eq_ignore_ascii_case(&hash)) {
+            write_file_atomic(file_path, &cached_data).await?;
+            return Ok(hash);
+        }
+        println!("⚠ Cached download for {} does not match expected hash; refetching", url);
+        purge_cached_download(url).await?;
+    }
+    let fetched = async {
+        let resp = client.get(url).send().await.map_err(|e| e.to_string())?;
+        resp.bytes().await.map_err(|e| e.to_string())
+    }.await;
+    let data = match fetched {
+        Ok(data) => data,
+        Err(e) => return use_existing_file_offline(file_path, expected_sha256).ok_or(e),
+    };
+    let hash = sha256_hex(&data);
+    if let Some(expected) = expected_sha256 {
+        if !expected.eq_ignore_ascii_case(&hash) {
+            purge_cached_download(url).await?;
+            let _ = tokio::fs::remove_file(file_path).await;
+            return Err(format!("SHA-256 mismatch for {}: expected {}, got {}", url, expected, hash));
+        }
     }
-    let resp = client.get(url).send().await.map_err(|e| e.to_string())?;
-    let data = resp.bytes().await.map_err(|e| e.to_string())?;
     let _ = cache_download(url, &data).await;
-    tokio::fs::write(file_path, data).await.map_err(|e| e.to_string())?;
-    Ok(())
+    write_file_atomic(file_path, &data).await?;
+    Ok(hash)
+}
+
+// Offline fallback: a file already seeded on disk (e.g. by an offline bundle import)
+// stands in for the download, provided it matches the expected hash when one is known.
+fn use_existing_file_offline(file_path: &Path, expected_sha256: Option<&str>) -> Option<String> {
+    let data = fs::read(file_path).ok()?;
+    let hash = sha256_hex(&data);
+    if !expected_sha256.is_none_or(|expected| expected.eq_ignore_ascii_case(&hash)) {
+        return None;
+    }
+    println!("⚠ Network unavailable; using existing file {}", file_path.display());
+    Some(hash)
+}
+
+// SHA-256 of each downloaded file, keyed by the path it was written to
+type DownloadHashes = HashMap<PathBuf, String>;
+
+// Helper: download several URLs concurrently (bounded by MAX_CONCURRENT_DOWNLOADS).
+// The first failure aborts the remaining downloads and is returned.
+async fn download_all_with_cache(client: &Client, downloads: &[MaterialDownload]) -> Result<DownloadHashes, String> {
+    // Each future owns its inputs so the stream stays Send across the await points
+    stream::iter(downloads.iter().cloned())
+        .map(|d| {
+            let client = client.clone();
+            async move {
+                download_to_file_with_cache(&client, &d.url, &d.path, d.sha256.as_deref())
+                    .await
+                    .map(|hash| (d.path, hash))
+                    .map_err(|e| format!("Download failed for {}: {}", d.url, e))
+            }
+        })
+        .buffer_unordered(MAX_CONCURRENT_DOWNLOADS)
+        .try_collect::<DownloadHashes>()
+        .await
+}
+
+// Helper: the vanilla material downloads used to uninstall RTX
+fn uninstall_downloads(dir: &Path) -> Vec<MaterialDownload> {
+    [
+        ("https://bedrock.graphics/api/uninstall/rtxstub", "RTXStub.material.bin"),
+        ("https://bedrock.graphics/api/uninstall/rtxpostfx", "RTXPostFX.Tonemapping.material.bin"),
+        ("https://bedrock.graphics/api/uninstall/bloom", "RTXPostFX.Bloom.material.bin"),
+    ]
+    .iter()
+    .map(|(url, file_name)| MaterialDownload {
+        url: url.to_string(),
+        path: dir.join(file_name),
+        sha256: None,
+    })
+    .collect()
+}
+
+// Helper: the three material file downloads of a preset
+fn preset_downloads(preset: &PackInfo, dir: &Path) -> Vec<MaterialDownload> {
+    vec![
+        MaterialDownload {
+            url: preset.stub.clone(),
+            path: dir.join("RTXStub.material.bin"),
+            sha256: preset.stub_sha256.clone(),
+        },
+        MaterialDownload {
+            url: preset.tonemapping.clone(),
+            path: dir.join("RTXPostFX.Tonemapping.material.bin"),
+            sha256: preset.tonemapping_sha256.clone(),
+        },
+        MaterialDownload {
+            url: preset.bloom.clone(),
+            path: dir.join("RTXPostFX.Bloom.material.bin"),
+            sha256: preset.bloom_sha256.clone(),
+        },
+    ]
+}
+
+#[tauri::command]
+async fn cancel_operation(operation_id: String) -> Result<operations::OperationReport, String> {
+    operations::cancel(&operation_id).await
+}
+
+// Queues an install-type command and returns its job ID without waiting for it
+#[tauri::command]
+fn enqueue_job(app_handle: tauri::AppHandle, request: JobRequest) -> Result<String, String> {
+    let (job_id, _) = jobs::enqueue(&app_handle, request)?;
+    Ok(job_id)
+}
+
+#[tauri::command]
+fn list_jobs() -> Result<Vec<jobs::JobInfo>, String> {
+    Ok(jobs::active())
+}
+
+#[tauri::command]
+fn get_job_status(job_id: String) -> Result<jobs::JobInfo, String> {
+    jobs::status(&job_id).ok_or_else(|| format!("Unknown job: {}", job_id))
+}
+
+#[tauri::command]
+fn get_job_history(limit: Option<usize>) -> Result<Vec<jobs::JobInfo>, String> {
+    Ok(jobs::history(limit.unwrap_or(50)))
+}
+
+// Journal entries for one installation, most recent first, optionally of one operation
+#[tauri::command]
+fn get_install_journal(install_location: String, operation: Option<JournalOperation>, limit: Option<usize>) -> Result<Vec<JournalEntry>, String> {
+    journal::query(&install_location, operation, limit.unwrap_or(100))
 }
 
 #[tauri::command]
@@ -700,258 +777,814 @@ async fn get_cache_info() -> Result<serde_json::Value, String> {
     Ok(serde_json::Value::Object(info))
 }
 
+const OFFLINE_BUNDLE_FORMAT_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct OfflineBundleManifest {
+    format_version: u32,
+    created_at: String,
+    presets: Vec<String>,
+    includes_uninstall: bool,
+    includes_dlss: bool,
+}
+
+fn zip_add_file<W: std::io::Write + std::io::Seek>(zip: &mut zip::ZipWriter<W>, name: &str, path: &Path) -> Result<(), String> {
+    let options = zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    zip.start_file(name, options).map_err(|e| e.to_string())?;
+    let mut f = File::open(path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    std::io::copy(&mut f, zip).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
 #[tauri::command]
-async fn download_and_install_pack(app_handle: tauri::AppHandle, uuid: String, selected_names: Vec<String>) -> Result<(), String> {
+async fn export_offline_bundle(dest_path: String, uuids: Option<Vec<String>>) -> Result<String, String> {
+    let packs = list_presets(false).await?;
+    let selected: Vec<&PackInfo> = match &uuids {
+        Some(wanted) => packs.iter().filter(|p| wanted.contains(&p.uuid)).collect(),
+        None => packs.iter().collect(),
+    };
+    if selected.is_empty() { return Err("No presets selected for the offline bundle".into()); }
+
+    // Make sure every file the bundle needs is on disk
+    let client = http_client()?;
+    let mut downloads = Vec::new();
+    for preset in &selected {
+        let dir = brtx_dir().join("packs").join(&preset.uuid);
+        ensure_dir(&dir).map_err(|e| e.to_string())?;
+        downloads.extend(preset_downloads(preset, &dir));
+    }
+    let uninstall_dir = brtx_dir().join("uninstall");
+    ensure_dir(&uninstall_dir).map_err(|e| e.to_string())?;
+    let uninstall = uninstall_downloads(&uninstall_dir);
+    downloads.extend(uninstall.iter().cloned());
+    // import_offline_bundle refuses archives over the extraction limits; bundle.json,
+    // catalog.json and the DLSS DLL are the other entries
+    if downloads.len() + 3 > archive::MAX_ENTRIES {
+        return Err(format!("Too many presets for one offline bundle ({}); select fewer", selected.len()));
+    }
+    download_all_with_cache(&client, &downloads).await?;
+    let dlss_dll = ensure_dlss_package().await?.join("nvngx_dlss.dll");
+
+    let dest = PathBuf::from(&dest_path);
+    let file = File::create(&dest).map_err(|e| format!("Failed to create bundle: {e}"))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+    let manifest = OfflineBundleManifest {
+        format_version: OFFLINE_BUNDLE_FORMAT_VERSION,
+        created_at: Utc::now().to_rfc3339(),
+        presets: selected.iter().map(|p| p.uuid.clone()).collect(),
+        includes_uninstall: true,
+        includes_dlss: dlss_dll.exists(),
+    };
+    zip.start_file("bundle.json", options).map_err(|e| e.to_string())?;
+    serde_json::to_writer_pretty(&mut zip, &manifest).map_err(|e| e.to_string())?;
+    zip.start_file("catalog.json", options).map_err(|e| e.to_string())?;
+    serde_json::to_writer_pretty(&mut zip, &packs).map_err(|e| e.to_string())?;
+
+    for d in &downloads {
+        let file_name = d.path.file_name().and_then(|n| n.to_str()).ok_or("Invalid material filename")?;
+        let folder = d.path.parent().and_then(|p| p.file_name()).and_then(|n| n.to_str()).ok_or("Invalid material path")?;
+        let entry = if d.path.starts_with(&uninstall_dir) {
+            format!("uninstall/{}", file_name)
+        } else {
+            format!("packs/{}/{}", folder, file_name)
+        };
+        zip_add_file(&mut zip, &entry, &d.path)?;
+    }
+    if dlss_dll.exists() {
+        zip_add_file(&mut zip, "dlss/nvngx_dlss.dll", &dlss_dll)?;
+    }
+    zip.finish().map_err(|e| e.to_string())?;
+
+    Ok(dest.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+async fn import_offline_bundle(bundle_path: String) -> Result<OfflineBundleManifest, String> {
+    let file = File::open(&bundle_path).map_err(|e| format!("Open bundle failed: {e}"))?;
+    let mut zip = zip::ZipArchive::new(file).map_err(|e| format!("Invalid offline bundle: {e}"))?;
+
+    let manifest: OfflineBundleManifest = {
+        let entry = zip.by_name("bundle.json").map_err(|_| "Offline bundle is missing bundle.json")?;
+        serde_json::from_reader(entry).map_err(|e| format!("Invalid bundle.json: {e}"))?
+    };
+    if manifest.format_version > OFFLINE_BUNDLE_FORMAT_VERSION {
+        return Err(format!("Unsupported offline bundle version {}", manifest.format_version));
+    }
+    let catalog: Vec<PackInfo> = {
+        let entry = zip.by_name("catalog.json").map_err(|_| "Offline bundle is missing catalog.json")?;
+        serde_json::from_reader(entry).map_err(|e| format!("Invalid catalog.json: {e}"))?
+    };
+
+    // Seed brtx_dir() with the bundled files; only the known top-level folders are accepted
+    let in_known_folder = |rel: &Path| {
+        let top = rel.components().next().map(|c| c.as_os_str().to_string_lossy().to_string());
+        matches!(top.as_deref(), Some("packs" | "uninstall" | "dlss"))
+    };
+    archive::extract_matching(&mut zip, &brtx_dir(), in_known_folder, |_| {}).map_err(|e| format!("Invalid offline bundle: {e}"))?;
+
+    // Seed the catalog cache; list_presets falls back to it once it expires offline
+    let _guard = CACHE_LOCK.lock().await;
+    let mut cache = load_cache().await;
+    let now = Utc::now();
+    cache.presets = Some(CacheEntry {
+        data: catalog,
+        timestamp: now,
+        expires_at: now + chrono::Duration::hours(1),
+    });
+    save_cache(&cache).await?;
+
+    Ok(manifest)
+}
+
+#[tauri::command]
+async fn download_and_install_pack(app_handle: tauri::AppHandle, uuid: String, selected_names: Vec<String>, operation_id: Option<String>, dry_run: Option<bool>) -> Result<CommandReport, String> {
+    if dry_run.unwrap_or(false) {
+        let all = list_installations(app_handle.clone()).await?;
+        let map: HashMap<_, _> = all.into_iter().map(|i| (i.install_location.clone(), i)).collect();
+        let packs = catalog_for_planning().await?;
+        let preset = packs.iter().find(|p| p.uuid == uuid).ok_or("Preset not found")?;
+        let downloads = preset_downloads(preset, &brtx_dir().join("packs").join(&uuid));
+        let mut plan = ActionPlan::new("install_preset");
+        plan.downloads = plan::plan_downloads(&downloads).await;
+        let names: Vec<String> = downloads.iter().filter_map(|d| d.path.file_name().map(|n| n.to_string_lossy().to_string())).collect();
+        let tracking = TrackingChange::Set { uuid: preset.uuid.clone(), name: preset.name.clone() };
+        plan::plan_material_targets(&mut plan, &names, &selected_names, &map, tracking);
+        return Ok(CommandReport::planned(plan));
+    }
+    jobs::run(&app_handle, JobRequest::InstallPreset { uuid, selected_names, operation_id }).await
+}
+
+async fn install_preset_job(app_handle: tauri::AppHandle, uuid: String, selected_names: Vec<String>, op: &Operation) -> Result<CommandReport, String> {
     let all = list_installations(app_handle.clone()).await?;
     // Map by InstallLocation because the UI sends InstallLocation values
     let map: std::collections::HashMap<_, _> = all
         .into_iter()
         .map(|i| (i.install_location.clone(), i))
         .collect();
-    
+
     // Get the preset info from cached API data
     let packs = get_api_packs().await?;
     let preset = packs.iter().find(|p| p.uuid == uuid).ok_or("Preset not found")?;
     
     let dir = brtx_dir().join("packs").join(&uuid);
     ensure_dir(&dir).map_err(|e| e.to_string())?;
-    let client = Client::new();
-    
-    // Download files with caching
-    let stub_path = dir.join("RTXStub.material.bin");
-    download_to_file_with_cache(&client, &preset.stub, &stub_path).await?;
-    let tone_path = dir.join("RTXPostFX.Tonemapping.material.bin");
-    download_to_file_with_cache(&client, &preset.tonemapping, &tone_path).await?;
-    let bloom_path = dir.join("RTXPostFX.Bloom.material.bin");
-    download_to_file_with_cache(&client, &preset.bloom, &bloom_path).await?;
-
-    let materials = vec![
-        stub_path.clone(),
-        tone_path.clone(),
-        bloom_path.clone(),
-    ];
+    let client = http_client()?;
+    
+    // Download files concurrently with caching
+    let downloads = preset_downloads(preset, &dir);
+    let hashes = op.cancellable(download_all_with_cache(&client, &downloads)).await?;
+
+    let materials: Vec<PathBuf> = downloads.into_iter().map(|d| d.path).collect();
+    let mut report = CommandReport::default();
     for install_location in selected_names {
-        if let Some(ins) = map.get(&install_location) {
-            copy_shader_files_async(&app_handle, &ins.install_location, &materials, preset).await?;
-        } else {
-            println!("⚠ Skipping unknown selection (no matching installation): {}", install_location);
+        if op.is_cancelled() {
+            report.cancelled(install_location);
+            continue;
+        }
+        let Some(ins) = map.get(&install_location) else {
+            report.skipped_unknown(install_location);
+            continue;
+        };
+        let result = copy_shader_files_async(&app_handle, &ins.install_location, &materials, preset, JournalOperation::Install, None, &hashes).await;
+        if result.is_ok() {
+            op.complete_target(&install_location);
         }
+        report.record(install_location, result);
     }
-    Ok(())
+    Ok(report)
+}
+
+#[tauri::command]
+async fn prefetch_presets(uuids: Option<Vec<String>>) -> Result<usize, String> {
+    let packs = list_presets(false).await?;
+    let selected: Vec<&PackInfo> = match &uuids {
+        Some(wanted) => packs.iter().filter(|p| wanted.contains(&p.uuid)).collect(),
+        None => packs.iter().collect(),
+    };
+
+    // Flatten every preset's files into one download set so the concurrency bound is global
+    let mut downloads = Vec::new();
+    for preset in &selected {
+        let dir = brtx_dir().join("packs").join(&preset.uuid);
+        ensure_dir(&dir).map_err(|e| e.to_string())?;
+        downloads.extend(preset_downloads(preset, &dir));
+    }
+
+    let client = http_client()?;
+    download_all_with_cache(&client, &downloads).await?;
+    Ok(selected.len())
+}
+
+// Name for a pack installed without a manifest: the file name without its extension,
+// or the folder name
+fn source_name(source: &Path, kind: import::SourceKind) -> Result<String, String> {
+    let name = match kind {
+        import::SourceKind::Archive => source.file_stem(),
+        import::SourceKind::Folder => source.file_name(),
+    };
+    Ok(name.and_then(|s| s.to_str()).ok_or("Invalid pack path")?.chars().take(20).collect())
+}
+
+// Dry run shared by install_from_rtpack and import_material_source
+async fn plan_source_install(app_handle: tauri::AppHandle, action: &str, source_path: &str, selected_names: &[String]) -> Result<CommandReport, String> {
+    let source = Path::new(source_path);
+    let all = list_installations(app_handle).await?;
+    let map: HashMap<_, _> = all.into_iter().map(|i| (i.install_location.clone(), i)).collect();
+    let settings = settings::load_settings();
+    let contents = import::survey(source, &settings.trusted_publishers)?;
+    signing::enforce(&contents.signature, settings.pack_signature_policy)?;
+    let mut plan = ActionPlan::new(action);
+    plan.downloads.push(plan::plan_local_file(source));
+    match &contents.manifest {
+        Some(manifest) => {
+            let tracking = TrackingChange::Set { uuid: manifest.uuid.clone(), name: manifest.name.clone() };
+            plan::plan_file_targets(&mut plan, &manifest.destinations(), selected_names, &map, tracking);
+        }
+        None => {
+            let tracking = TrackingChange::Set {
+                uuid: format!("material-files-{}", chrono::Utc::now().timestamp()),
+                name: source_name(source, contents.kind)?,
+            };
+            plan::plan_material_targets(&mut plan, &contents.material_names(), selected_names, &map, tracking);
+        }
+    }
+    plan::warn_material_versions(&mut plan.targets, &contents.material_versions(source)?);
+    Ok(CommandReport { signature: Some(contents.signature), ..CommandReport::planned(plan) })
 }
 
 #[tauri::command]
-async fn install_from_rtpack(app_handle: tauri::AppHandle, rtpack_path: String, selected_names: Vec<String>) -> Result<(), String> {
+async fn install_from_rtpack(app_handle: tauri::AppHandle, rtpack_path: String, selected_names: Vec<String>, dry_run: Option<bool>) -> Result<CommandReport, String> {
     if !rtpack_path.to_ascii_lowercase().ends_with(".rtpack") { return Err("Invalid file type; expected .rtpack".into()); }
-    let pack_name = Path::new(&rtpack_path).file_stem().and_then(|s| s.to_str()).ok_or("Invalid pack path")?.to_string();
-    let out_dir = brtx_dir().join("packs").join(&pack_name);
-    extract_rtpack_to(Path::new(&rtpack_path), &out_dir)?;
-    let materials = find_materials(&out_dir);
-    if materials.is_empty() { return Err("No materials found in pack".into()); }
+    if dry_run.unwrap_or(false) {
+        return plan_source_install(app_handle, "install_rtpack", &rtpack_path, &selected_names).await;
+    }
+    jobs::run(&app_handle, JobRequest::InstallRtpack { rtpack_path, selected_names }).await
+}
+
+// Installs materials from a .rtpack, .mcpack or .zip archive or a folder; the kind is
+// detected from the content, so the extension does not matter
+#[tauri::command]
+async fn import_material_source(app_handle: tauri::AppHandle, source_path: String, selected_names: Vec<String>, dry_run: Option<bool>) -> Result<CommandReport, String> {
+    if dry_run.unwrap_or(false) {
+        return plan_source_install(app_handle, "import_material_source", &source_path, &selected_names).await;
+    }
+    jobs::run(&app_handle, JobRequest::ImportMaterialSource { source_path, selected_names }).await
+}
+
+// Catalog presets with at least one known hash where every known hash matches the
+// material of the same name in `materials`
+fn matching_presets(materials: &[rtpack::PackEntry], presets: &[PackInfo]) -> Vec<rtpack::PresetMatch> {
+    let hash_of = |file_name: &str| {
+        materials
+            .iter()
+            .find(|m| Path::new(&m.path.replace('\\', "/")).file_name().is_some_and(|n| n == file_name))
+            .and_then(|m| m.sha256.as_deref())
+    };
+    presets
+        .iter()
+        .filter(|preset| {
+            let known: Vec<(&str, &str)> = RTX_MATERIAL_FILES
+                .iter()
+                .zip([&preset.stub_sha256, &preset.tonemapping_sha256, &preset.bloom_sha256])
+                .filter_map(|(file, hash)| hash.as_deref().map(|h| (*file, h)))
+                .collect();
+            !known.is_empty() && known.iter().all(|(file, hash)| hash_of(file).is_some_and(|h| h.eq_ignore_ascii_case(hash)))
+        })
+        .map(|preset| rtpack::PresetMatch { uuid: preset.uuid.clone(), name: preset.name.clone() })
+        .collect()
+}
+
+// Whether a dropped path is something import_material_source can install from
+#[tauri::command]
+fn detect_material_source(path: String) -> Result<import::SourceKind, String> {
+    import::detect(Path::new(&path))
+}
+
+// Reads a pack without extracting it, for showing what an install would do
+#[tauri::command]
+async fn inspect_rtpack(rtpack_path: String) -> Result<rtpack::PackInspection, String> {
+    let settings = settings::load_settings();
+    let mut inspection = rtpack::inspect(Path::new(&rtpack_path), &settings.trusted_publishers)?;
+    match get_api_packs().await {
+        Ok(presets) => inspection.matching_presets = matching_presets(&inspection.materials, &presets),
+        Err(e) => inspection.warnings.push(format!("Preset catalog unavailable: {}", e)),
+    }
+    Ok(inspection)
+}
+
+#[derive(Serialize, Clone)]
+struct ExtractProgressEvent<'a> {
+    archive: &'a str,
+    #[serde(flatten)]
+    progress: archive::ExtractProgress,
+}
+
+// Keeps an extraction directory from being cleaned up until it is dropped
+struct PackInUse(String);
+
+impl PackInUse {
+    fn new(pack_hash: &str) -> Self {
+        PACKS_IN_USE.lock().unwrap_or_else(|e| e.into_inner()).push(pack_hash.to_string());
+        PackInUse(pack_hash.to_string())
+    }
+}
+
+impl Drop for PackInUse {
+    fn drop(&mut self) {
+        let mut in_use = PACKS_IN_USE.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(i) = in_use.iter().position(|h| *h == self.0) {
+            in_use.remove(i);
+        }
+    }
+}
+
+async fn install_source_job(app_handle: tauri::AppHandle, source_path: String, selected_names: Vec<String>) -> Result<CommandReport, String> {
+    let source = Path::new(&source_path);
+    let settings = settings::load_settings();
+    let kind = import::detect(source)?;
+    let extracted_dir = brtx_dir().join("packs").join("extracted");
+    // Archives are extracted into a directory named by their hash; folders install in place
+    let (manifest, signature, materials, base_dir, pack_hash, in_use) = match kind {
+        import::SourceKind::Archive => {
+            let pack_hash = rtpack::pack_hash(source)?;
+            let out_dir = extracted_dir.join(&pack_hash);
+            let lock = EXTRACT_LOCK.lock().await;
+            let (trusted, policy) = (&settings.trusted_publishers, settings.pack_signature_policy);
+            // Another job may be installing the same pack from this directory right now
+            let busy = PACKS_IN_USE.lock().unwrap_or_else(|e| e.into_inner()).contains(&pack_hash);
+            let rtpack::ExtractedPack { manifest, signature, files } = if busy {
+                rtpack::already_extracted(source, &out_dir, trusted, policy)?
+            } else {
+                rtpack::extract(source, &out_dir, trusted, policy, |progress| {
+                    let _ = app_handle.emit("extract-progress", ExtractProgressEvent { archive: &source_path, progress });
+                })?
+            };
+            let materials = match manifest {
+                Some(_) => Vec::new(),
+                None => import::select_materials(files.iter().filter_map(|f| f.strip_prefix(&out_dir).ok()).map(Path::to_path_buf).collect())?,
+            };
+            // Installs run without the lock; this keeps cleanup away from the directory meanwhile
+            let in_use = PackInUse::new(&pack_hash);
+            drop(lock);
+            (manifest, signature, materials, out_dir, Some(pack_hash), Some(in_use))
+        }
+        import::SourceKind::Folder => {
+            let contents = import::survey(source, &settings.trusted_publishers)?;
+            signing::enforce(&contents.signature, settings.pack_signature_policy)?;
+            (contents.manifest, contents.signature, contents.materials, source.to_path_buf(), None, None)
+        }
+    };
+    if manifest.is_none() && materials.is_empty() { return Err("No materials found in pack".into()); }
+    let materials: Vec<PathBuf> = materials.iter().map(|m| base_dir.join(m)).collect();
+    // Packs without a manifest are named after the source and get a generated UUID
+    let pack = match &manifest {
+        Some(manifest) => PackInfo {
+            name: manifest.name.clone(),
+            uuid: manifest.uuid.clone(),
+            stub: String::new(),
+            tonemapping: String::new(),
+            bloom: String::new(),
+            stub_sha256: None,
+            tonemapping_sha256: None,
+            bloom_sha256: None,
+        },
+        None => PackInfo {
+            name: source_name(source, kind)?,
+            uuid: format!("material-files-{}", chrono::Utc::now().timestamp()),
+            stub: String::new(),
+            tonemapping: String::new(),
+            bloom: String::new(),
+            stub_sha256: None,
+            tonemapping_sha256: None,
+            bloom_sha256: None,
+        },
+    };
     let all = list_installations(app_handle.clone()).await?;
     let map: std::collections::HashMap<_, _> = all
         .into_iter()
         .map(|i| (i.install_location.clone(), i))
         .collect();
+    let mut report = CommandReport { signature: Some(signature), ..CommandReport::default() };
     for install_location in selected_names {
-        if let Some(ins) = map.get(&install_location) {
-            // Create a dummy pack for material file installation
-            let dummy_pack = PackInfo {
-                name: pack_name.clone().chars().take(20).collect(),
-                uuid: format!("material-files-{}", chrono::Utc::now().timestamp()),
-                stub: String::new(),
-                tonemapping: String::new(),
-                bloom: String::new(),
-            };
-            copy_shader_files_async(&app_handle, &ins.install_location, &materials, &dummy_pack).await?;
-        } else {
-            println!("⚠ Skipping unknown selection (no matching installation): {}", install_location);
+        let Some(ins) = map.get(&install_location) else {
+            report.skipped_unknown(install_location);
+            continue;
+        };
+        let result = match &manifest {
+            Some(manifest) => match manifest.install_pairs(&base_dir, Path::new(&ins.install_location)) {
+                Ok(pairs) => install_pack_files(&app_handle, &ins.install_location, &pairs, &pack, JournalOperation::Install, Some(&source_path), &DownloadHashes::new()).await,
+                Err(e) => Err(e),
+            },
+            None => copy_shader_files_async(&app_handle, &ins.install_location, &materials, &pack, JournalOperation::Install, Some(&source_path), &DownloadHashes::new()).await,
+        };
+        if result.is_ok() && pack_hash.is_some() {
+            let _lock = EXTRACT_LOCK.lock().await;
+            if let Some(mut installed) = get_installed_preset(&ins.install_location) {
+                installed.rtpack_sha256 = pack_hash.clone();
+                if let Err(e) = save_installed_preset(&ins.install_location, &installed) {
+                    println!("⚠ Failed to save preset tracking: {}", e);
+                }
+            }
+        }
+        report.record(install_location, result);
+    }
+    let _lock = EXTRACT_LOCK.lock().await;
+    drop(in_use);
+    let mut referenced = referenced_pack_hashes();
+    referenced.extend(PACKS_IN_USE.lock().unwrap_or_else(|e| e.into_inner()).iter().cloned());
+    let removed = rtpack::remove_unreferenced(&extracted_dir, &referenced.iter().map(String::as_str).collect::<Vec<_>>());
+    if removed > 0 {
+        println!("Removed {} unused pack extraction(s)", removed);
+    }
+    Ok(report)
+}
+
+#[tauri::command]
+async fn install_materials(app_handle: tauri::AppHandle, material_paths: Vec<String>, selected_names: Vec<String>, dry_run: Option<bool>) -> Result<CommandReport, String> {
+    if material_paths.is_empty() { return Err("No files provided".into()); }
+    if dry_run.unwrap_or(false) {
+        let materials: Vec<PathBuf> = material_paths.iter().map(PathBuf::from).collect();
+        let all = list_installations(app_handle.clone()).await?;
+        let map: HashMap<_, _> = all.into_iter().map(|i| (i.install_location.clone(), i)).collect();
+        let mut plan = ActionPlan::new("install_materials");
+        plan.downloads = materials.iter().map(|m| plan::plan_local_file(m)).collect();
+        let tracking = TrackingChange::Set { uuid: "material-files".to_string(), name: "Material Files".to_string() };
+        plan::plan_material_targets(&mut plan, &material_file_names(&materials), &selected_names, &map, tracking);
+        plan::warn_material_versions(&mut plan.targets, &material::file_versions(&materials));
+        return Ok(CommandReport::planned(plan));
+    }
+    jobs::run(&app_handle, JobRequest::InstallMaterials { material_paths, selected_names }).await
+}
+
+async fn install_materials_job(app_handle: tauri::AppHandle, material_paths: Vec<String>, selected_names: Vec<String>) -> Result<CommandReport, String> {
+    let materials: Vec<PathBuf> = material_paths.iter().map(PathBuf::from).collect();
+    let all = list_installations(app_handle.clone()).await?;
+    let map: std::collections::HashMap<_, _> = all.into_iter().map(|i| (i.install_location.clone(), i)).collect();
+    let source = material_paths.join("; ");
+    // Create a dummy pack for material file installation
+    let dummy_pack = creator_pack("Material Files".to_string(), "material-files".to_string());
+    let mut report = CommandReport::default();
+    for install_location in selected_names {
+        let Some(ins) = map.get(&install_location) else {
+            report.skipped_unknown(install_location);
+            continue;
+        };
+        let result = copy_shader_files_async(&app_handle, &ins.install_location, &materials, &dummy_pack, JournalOperation::Install, Some(&source), &DownloadHashes::new()).await;
+        report.record(install_location, result);
+    }
+    Ok(report)
+}
+
+async fn backup_initial_shader_files(app_handle: &tauri::AppHandle, location: &str, backup_dir: &Path) -> Result<(), String> {
+    ensure_dir(backup_dir).map_err(|e| e.to_string())?;
+    let mut pairs = Vec::new();
+    let dlss = Path::new(location).join("nvngx_dlss.dll");
+    if dlss.exists() { pairs.push((dlss, backup_dir.join("nvngx_dlss.dll"))); }
+    let mc_src = Path::new(location).join("data").join("renderer").join("materials");
+    for m in RTX_MATERIAL_FILES { let src = mc_src.join(m); if src.exists() { pairs.push((src, backup_dir.join(m))); } }
+    if pairs.is_empty() { return Ok(()); }
+    backend::copy_files(app_handle, backup_dir, &pairs).await?;
+    Ok(())
+}
+
+fn zip_dir(src_dir: &Path, dest_zip: &Path) -> Result<(), String> {
+    let file = File::create(dest_zip).map_err(|e| e.to_string())?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    let src = src_dir.to_path_buf();
+    for entry in WalkDir::new(&src).into_iter().filter_map(Result::ok) {
+        let path = entry.path();
+        let name = path.strip_prefix(&src).map_err(|e| e.to_string())?.to_string_lossy().replace('\\', "/");
+        if path.is_file() {
+            zip.start_file(name, options).map_err(|e| e.to_string())?;
+            let mut f = File::open(path).map_err(|e| e.to_string())?;
+            std::io::copy(&mut f, &mut zip).map_err(|e| e.to_string())?;
+        } else if !name.is_empty() {
+            zip.add_directory(name + "/", options).map_err(|e| e.to_string())?;
         }
     }
+    zip.finish().map_err(|e| e.to_string())?;
     Ok(())
 }
 
+// The .rtpack a backup of `ins` made now would be written to
+fn backup_path(ins: &Installation, dest: &Path) -> PathBuf {
+    let instance = ins.install_location.split(['\\', '/']).next_back().unwrap_or("instance").replace(' ', "_");
+    let ts = Local::now().format("%Y-%m-%d_%H-%M");
+    dest.join(format!("betterrtx_backup_{}_{}.rtpack", instance, ts))
+}
+
+async fn backup_installation(app_handle: &tauri::AppHandle, ins: &Installation, dest: &Path) -> Result<String, String> {
+    // Staged per job: installations can share a friendly name, and backup/<friendly name>
+    // holds the initial backup that uninstall_package restores
+    let backup_dir = brtx_dir().join("backup-staging").join(unique_name("backup"));
+    let result = async {
+        backup_initial_shader_files(app_handle, &ins.install_location, &backup_dir).await?;
+        let rtpack = backup_path(ins, dest);
+        let zip_path = rtpack.with_extension("zip");
+        zip_dir(&backup_dir, &zip_path)?;
+        fs::rename(&zip_path, &rtpack).map_err(|e| e.to_string())?;
+        Ok(rtpack.to_string_lossy().to_string())
+    }
+    .await;
+    // Clean temp backup dir, whether or not the backup worked
+    let _ = fs::remove_dir_all(&backup_dir);
+    result
+}
+
+#[tauri::command]
+async fn backup_selected(app_handle: tauri::AppHandle, dest_dir: String, selected_names: Option<Vec<String>>, operation_id: Option<String>, dry_run: Option<bool>) -> Result<CommandReport, String> {
+    if !Path::new(&dest_dir).exists() { return Err("Destination directory does not exist".into()); }
+    // Without a selection every installation is backed up
+    let selected_names = match selected_names {
+        Some(names) => names,
+        None => list_installations(app_handle.clone()).await?.into_iter().map(|i| i.install_location).collect(),
+    };
+    if dry_run.unwrap_or(false) {
+        let all = list_installations(app_handle.clone()).await?;
+        let map: HashMap<_, _> = all.into_iter().map(|i| (i.install_location.clone(), i)).collect();
+        let mut plan = ActionPlan::new("backup");
+        plan::plan_output_targets(&mut plan, &selected_names, &map, |ins| backup_path(ins, Path::new(&dest_dir)));
+        return Ok(CommandReport::planned(plan));
+    }
+    jobs::run(&app_handle, JobRequest::Backup { dest_dir, selected_names, operation_id }).await
+}
+
+async fn backup_job(app_handle: tauri::AppHandle, dest_dir: String, selected_names: Vec<String>, op: &Operation) -> Result<CommandReport, String> {
+    let dest = PathBuf::from(dest_dir);
+    if !dest.exists() { return Err("Destination directory does not exist".into()); }
+    let all = list_installations(app_handle.clone()).await?;
+    let mut report = CommandReport::default();
+    // UI sends InstallLocation values in selected_names
+    for name in selected_names.iter().filter(|n| !all.iter().any(|i| &i.install_location == *n)) {
+        report.skipped_unknown(name.clone());
+    }
+    let targets: Vec<Installation> = all
+        .into_iter()
+        .filter(|i| selected_names.contains(&i.install_location))
+        .collect();
+    for ins in targets {
+        if op.is_cancelled() {
+            report.cancelled(ins.install_location);
+            continue;
+        }
+        let result = backup_installation(&app_handle, &ins, &dest).await;
+        if result.is_ok() {
+            op.complete_target(&ins.install_location);
+        }
+        report.record_output(ins.install_location, result);
+    }
+    Ok(report)
+}
+
+// A catalog preset and its material files, downloaded if they are not cached yet
+async fn download_preset_materials(uuid: &str) -> Result<(PackInfo, Vec<PathBuf>), String> {
+    let packs = get_api_packs().await?;
+    let preset = packs.into_iter().find(|p| p.uuid == uuid).ok_or("Preset not found")?;
+    let dir = brtx_dir().join("packs").join(uuid);
+    ensure_dir(&dir).map_err(|e| e.to_string())?;
+    let downloads = preset_downloads(&preset, &dir);
+    download_all_with_cache(&http_client()?, &downloads).await?;
+    Ok((preset, downloads.into_iter().map(|d| d.path).collect()))
+}
+
+// What export_rtpack packs up
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case", rename_all_fields = "camelCase")]
+enum ExportSource {
+    // The RTX materials currently in an installation
+    Installation { install_location: String },
+    // A preset from the API, downloaded if it is not cached yet
+    Preset { uuid: String },
+    // The creator tab's upload folder
+    CreatorUploads,
+}
+
+// Package version from the installation's AppxManifest.xml, e.g. 1.21.5101.0
+fn installed_game_version(install_location: &str) -> Option<String> {
+    let xml = fs::read_to_string(Path::new(install_location).join("AppxManifest.xml")).ok()?;
+    let identity = &xml[xml.find("<Identity")?..];
+    let identity = &identity[..identity.find('>')?];
+    let start = identity.find("Version=\"")? + "Version=\"".len();
+    let len = identity[start..].find('"')?;
+    Some(identity[start..start + len].to_string())
+}
+
+// Short stable ID for a set of files, so exporting the same files twice gives the same UUID
+fn content_id(hashes: &HashMap<String, String>) -> String {
+    let mut entries: Vec<String> = hashes.iter().map(|(name, hash)| format!("{name}:{hash}")).collect();
+    entries.sort();
+    sha256_hex(entries.join("\n").as_bytes())[..12].to_string()
+}
+
+// Builds an .rtpack with a generated manifest in `dest_dir` and returns its path
+#[tauri::command]
+async fn export_rtpack(source: ExportSource, dest_dir: String, name: Option<String>, author: Option<String>, description: Option<String>) -> Result<String, String> {
+    let dest = PathBuf::from(dest_dir);
+    if !dest.exists() { return Err("Destination directory does not exist".into()); }
+    let (mut manifest, materials) = match source {
+        ExportSource::Installation { install_location } => {
+            let mc_src = Path::new(&install_location).join("data").join("renderer").join("materials");
+            let materials: Vec<PathBuf> = RTX_MATERIAL_FILES.iter().map(|m| mc_src.join(m)).filter(|p| p.exists()).collect();
+            if materials.is_empty() { return Err(format!("No RTX materials found in {}", install_location)); }
+            let hashes = hash_material_files(&materials);
+            let preset = get_installed_preset(&install_location);
+            // The tracked preset only names these files if none of them changed since it was installed
+            let unchanged = preset.as_ref().and_then(|p| p.material_hashes.as_ref()).is_some_and(|tracked| {
+                !tracked.is_empty() && tracked.iter().all(|(file, hash)| hashes.get(file) == Some(hash))
+            });
+            let mut manifest = match &preset {
+                Some(p) if unchanged => rtpack::RtpackManifest::new(&p.name, &p.uuid),
+                Some(p) => rtpack::RtpackManifest::new(&format!("{} (modified)", p.name), &format!("export-{}", content_id(&hashes))),
+                None => rtpack::RtpackManifest::new("Exported Materials", &format!("export-{}", content_id(&hashes))),
+            };
+            manifest.source_preset = preset.map(|p| p.uuid);
+            manifest.game_versions = installed_game_version(&install_location).into_iter().collect();
+            (manifest, materials)
+        }
+        ExportSource::Preset { uuid } => {
+            let (preset, materials) = download_preset_materials(&uuid).await?;
+            let mut manifest = rtpack::RtpackManifest::new(&preset.name, &preset.uuid);
+            manifest.source_preset = Some(preset.uuid.clone());
+            (manifest, materials)
+        }
+        ExportSource::CreatorUploads => {
+            let materials = uploaded_materials(&brtx_dir().join("creator").join("uploaded"))?;
+            let uuid = format!("materials-{}", content_id(&hash_material_files(&materials)));
+            (rtpack::RtpackManifest::new("Uploaded Materials", &uuid), materials)
+        }
+    };
+    if let Some(name) = name.filter(|n| !n.trim().is_empty()) { manifest.name = name; }
+    manifest.author = author;
+    manifest.description = description;
+
+    let files = materials
+        .iter()
+        .map(|m| {
+            let file_name = m.file_name().ok_or_else(|| format!("Invalid material filename: {}", m.display()))?;
+            Ok((m.clone(), format!("data/renderer/materials/{}", file_name.to_string_lossy())))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    let safe_name: String = manifest.name.chars().map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' }).collect();
+    let ts = Local::now().format("%Y-%m-%d_%H-%M");
+    let pack = dest.join(format!("{}_{}.rtpack", safe_name, ts));
+    rtpack::write_pack(&pack, manifest, &files)?;
+    Ok(pack.to_string_lossy().to_string())
+}
+
+// Metadata of a material.bin file, or an error if it is not a valid material
 #[tauri::command]
-async fn install_materials(app_handle: tauri::AppHandle, material_paths: Vec<String>, selected_names: Vec<String>) -> Result<(), String> {
-    if material_paths.is_empty() { return Err("No files provided".into()); }
-    let materials: Vec<PathBuf> = material_paths.iter().map(PathBuf::from).collect();
-    let all = list_installations(app_handle.clone()).await?;
-    let map: std::collections::HashMap<_, _> = all.into_iter().map(|i| (i.install_location.clone(), i)).collect();
-    for install_location in selected_names {
-        if let Some(ins) = map.get(&install_location) {
-            // Create a dummy pack for material file installation
-            let dummy_pack = PackInfo {
-                name: "Material Files".to_string(),
-                uuid: "material-files".to_string(),
-                stub: String::new(),
-                tonemapping: String::new(),
-                bloom: String::new(),
+fn read_material_info(path: String) -> Result<material::MaterialInfo, String> {
+    material::parse_file(Path::new(&path))
+}
+
+// One side of diff_material_sources
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case", rename_all_fields = "camelCase")]
+enum DiffSource {
+    // An .rtpack (including backups), .mcpack or .zip archive, or a folder
+    Pack { path: String },
+    Preset { uuid: String },
+    // The RTX materials currently in an installation
+    Installation { install_location: String },
+}
+
+// The source's material files keyed by file name, read into memory
+async fn diff_source_files(source: DiffSource) -> Result<BTreeMap<String, Vec<u8>>, String> {
+    let read = |path: &Path| fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e));
+    let named_files = |paths: Vec<PathBuf>| {
+        paths
+            .into_iter()
+            .map(|p| Ok((p.file_name().unwrap_or_default().to_string_lossy().to_string(), read(&p)?)))
+            .collect::<Result<BTreeMap<_, _>, String>>()
+    };
+    match source {
+        DiffSource::Pack { path } => {
+            let path = Path::new(&path);
+            let contents = import::survey(path, &settings::load_settings().trusted_publishers)?;
+            let (names, paths): (Vec<String>, Vec<PathBuf>) = contents.installed_files().into_iter().unzip();
+            let data = match contents.kind {
+                import::SourceKind::Archive => rtpack::read_files(path, &paths)?,
+                import::SourceKind::Folder => paths.iter().map(|p| read(&path.join(p))).collect::<Result<_, _>>()?,
             };
-            copy_shader_files_async(&app_handle, &ins.install_location, &materials, &dummy_pack).await?;
-        } else {
-            println!("⚠ Skipping unknown selection (no matching installation): {}", install_location);
+            Ok(names.into_iter().zip(data).collect())
+        }
+        DiffSource::Preset { uuid } => named_files(download_preset_materials(&uuid).await?.1),
+        DiffSource::Installation { install_location } => {
+            let mc_src = Path::new(&install_location).join("data").join("renderer").join("materials");
+            named_files(RTX_MATERIAL_FILES.iter().map(|m| mc_src.join(m)).filter(|p| p.exists()).collect())
         }
     }
-    Ok(())
 }
 
-fn backup_initial_shader_files(location: &str, backup_dir: &Path) -> Result<(), String> {
-    ensure_dir(backup_dir).map_err(|e| e.to_string())?;
-    let dlss = Path::new(location).join("nvngx_dlss.dll");
-    if dlss.exists() { let _ = fs::copy(&dlss, backup_dir.join("nvngx_dlss.dll")); }
-    let materials = [
-        "RTXStub.material.bin",
-        "RTXPostFX.Tonemapping.material.bin",
-        "RTXPostFX.Bloom.material.bin",
-    ];
-    let mc_src = Path::new(location).join("data").join("renderer").join("materials");
-    for m in materials { let src = mc_src.join(m); if src.exists() { let _ = fs::copy(&src, backup_dir.join(m)); } }
-    Ok(())
+// Per-file differences between two material sources, e.g. an installed preset and a
+// new version of its pack
+#[tauri::command]
+async fn diff_material_sources(before: DiffSource, after: DiffSource) -> Result<Vec<diff::MaterialDiff>, String> {
+    let before = diff_source_files(before).await?;
+    let after = diff_source_files(after).await?;
+    Ok(diff::diff(&before, &after))
 }
 
-fn zip_dir(src_dir: &Path, dest_zip: &Path) -> Result<(), String> {
-    let file = File::create(dest_zip).map_err(|e| e.to_string())?;
-    let mut zip = zip::ZipWriter::new(file);
-    let options = zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
-    let src = src_dir.to_path_buf();
-    for entry in WalkDir::new(&src).into_iter().filter_map(Result::ok) {
-        let path = entry.path();
-        let name = path.strip_prefix(&src).map_err(|e| e.to_string())?.to_string_lossy().replace('\\', "/");
-        if path.is_file() {
-            zip.start_file(name, options).map_err(|e| e.to_string())?;
-            let mut f = File::open(path).map_err(|e| e.to_string())?;
-            std::io::copy(&mut f, &mut zip).map_err(|e| e.to_string())?;
-        } else if !name.is_empty() {
-            zip.add_directory(name + "/", options).map_err(|e| e.to_string())?;
-        }
+// Download and extract the DLSS package into brtx_dir()/dlss unless it is already there
+async fn ensure_dlss_package() -> Result<PathBuf, String> {
+    let dir = brtx_dir().join("dlss");
+    let _lock = DLSS_LOCK.lock().await;
+    if dir.join("nvngx_dlss.dll").exists() {
+        return Ok(dir);
     }
-    zip.finish().map_err(|e| e.to_string())?;
-    Ok(())
+    // Download into a staging directory and move it into place once complete, so a
+    // failed or cancelled download never leaves a partial package behind
+    let staging = brtx_dir().join("dlss.download");
+    let _ = fs::remove_dir_all(&staging);
+    ensure_dir(&staging).map_err(|e| e.to_string())?;
+    let client = http_client()?;
+    let versions: serde_json::Value = client.get("https://bedrock.graphics/api/dlss").send().await.map_err(|e| e.to_string())?.json().await.map_err(|e| e.to_string())?;
+    let latest = versions.get("latest").and_then(|v| v.as_str()).ok_or("Invalid DLSS API response")?;
+    let zip_path = staging.join("nvngx_dlss.zip");
+    let resp = client.get(latest).send().await.map_err(|e| e.to_string())?;
+    let bytes = resp.bytes().await.map_err(|e| e.to_string())?;
+    tokio::fs::write(&zip_path, bytes).await.map_err(|e| e.to_string())?;
+    // extract
+    let file = File::open(&zip_path).map_err(|e| e.to_string())?;
+    let mut zip = zip::ZipArchive::new(std::io::BufReader::new(file)).map_err(|e| e.to_string())?;
+    archive::extract_all(&mut zip, &staging)?;
+    let _ = fs::remove_file(&zip_path);
+    let _ = fs::remove_dir_all(&dir);
+    fs::rename(&staging, &dir).map_err(|e| format!("Failed to move DLSS package into place: {e}"))?;
+    Ok(dir)
 }
 
 #[tauri::command]
-async fn backup_selected(app_handle: tauri::AppHandle, dest_dir: String, selected_names: Option<Vec<String>>) -> Result<Vec<String>, String> {
-    let dest = PathBuf::from(dest_dir);
-    if !dest.exists() { return Err("Destination directory does not exist".into()); }
-    let all = list_installations(app_handle).await?;
-    // UI sends InstallLocation values in selected_names
-    let targets: Vec<Installation> = if let Some(names) = selected_names {
-        all
-            .into_iter()
-            .filter(|i| names.contains(&i.install_location))
-            .collect()
-    } else {
-        all
-    };
-    let mut created = Vec::new();
-    for ins in targets {
-        let instance = ins.install_location.split(['\\', '/']).last().unwrap_or("instance").replace(' ', "_");
-        let backup_dir = brtx_dir().join("backup").join(&ins.friendly_name);
-        ensure_dir(backup_dir.parent().unwrap()) .map_err(|e| e.to_string())?;
-        ensure_dir(&backup_dir).map_err(|e| e.to_string())?;
-        backup_initial_shader_files(&ins.install_location, &backup_dir)?;
-        let ts = Local::now().format("%Y-%m-%d_%H-%M");
-        let zip_path = dest.join(format!("betterrtx_backup_{}_{}.zip", instance, ts));
-        zip_dir(&backup_dir, &zip_path)?;
-        let rtpack = zip_path.with_extension("rtpack");
-        fs::rename(&zip_path, &rtpack).map_err(|e| e.to_string())?;
-        // Clean temp backup dir
-        let _ = fs::remove_dir_all(&backup_dir);
-        created.push(rtpack.to_string_lossy().to_string());
+async fn install_dlss_for_selected(app_handle: tauri::AppHandle, selected_names: Vec<String>, operation_id: Option<String>, dry_run: Option<bool>) -> Result<CommandReport, String> {
+    if dry_run.unwrap_or(false) {
+        let all = list_installations(app_handle.clone()).await?;
+        let map: HashMap<_, _> = all.into_iter().map(|i| (i.install_location.clone(), i)).collect();
+        let mut plan = ActionPlan::new("install_dlss");
+        let dll = brtx_dir().join("dlss").join("nvngx_dlss.dll");
+        plan.downloads.push(if dll.exists() {
+            plan::plan_local_file(&dll)
+        } else {
+            PlannedDownload {
+                url: "https://bedrock.graphics/api/dlss".to_string(),
+                path: dll.to_string_lossy().to_string(),
+                source: plan::DownloadSource::Network,
+            }
+        });
+        plan::plan_root_file_targets(&mut plan, "nvngx_dlss.dll", &selected_names, &map);
+        return Ok(CommandReport::planned(plan));
     }
-    Ok(created)
+    jobs::run(&app_handle, JobRequest::InstallDlss { selected_names, operation_id }).await
 }
 
-#[tauri::command]
-async fn install_dlss_for_selected(app_handle: tauri::AppHandle, selected_names: Vec<String>) -> Result<(), String> {
-    let dir = brtx_dir().join("dlss");
-    if !dir.exists() {
-        ensure_dir(&dir).map_err(|e| e.to_string())?;
-        let client = Client::new();
-        let versions: serde_json::Value = client.get("https://bedrock.graphics/api/dlss").send().await.map_err(|e| e.to_string())?.json().await.map_err(|e| e.to_string())?;
-        let latest = versions.get("latest").and_then(|v| v.as_str()).ok_or("Invalid DLSS API response")?;
-        let zip_path = dir.join("nvngx_dlss.zip");
-        let resp = client.get(latest).send().await.map_err(|e| e.to_string())?;
-        let bytes = resp.bytes().await.map_err(|e| e.to_string())?;
-        tokio::fs::write(&zip_path, bytes).await.map_err(|e| e.to_string())?;
-        // extract
-        extract_rtpack_to(&zip_path, &dir).or_else(|_| {
-            // Some DLSS zips may not be .rtpack format; try normal zip extraction path
-            let file = File::open(&zip_path).map_err(|e| e.to_string())?;
-            let mut zip = zip::ZipArchive::new(file).map_err(|e| e.to_string())?;
-            for i in 0..zip.len() {
-                let mut file = zip.by_index(i).map_err(|e| e.to_string())?;
-                let out = dir.join(file.name());
-                if file.name().ends_with('/') { ensure_dir(&out).map_err(|e| e.to_string())?; } else {
-                    if let Some(par) = out.parent() { ensure_dir(par).map_err(|e| e.to_string())?; }
-                    let mut of = File::create(&out).map_err(|e| e.to_string())?;
-                    std::io::copy(&mut file, &mut of).map_err(|e| e.to_string())?;
-                }
-            }
-            Ok::<(), String>(())
-        })?;
-        let _ = fs::remove_file(&zip_path);
-    }
+async fn install_dlss_job(app_handle: tauri::AppHandle, selected_names: Vec<String>, op: &Operation) -> Result<CommandReport, String> {
+    let dir = op.cancellable(ensure_dlss_package()).await?;
 
     let all = list_installations(app_handle.clone()).await?;
     let map: std::collections::HashMap<_, _> = all
         .into_iter()
         .map(|i| (i.install_location.clone(), i))
         .collect();
+    let src = dir.join("nvngx_dlss.dll");
+    if !src.exists() { return Err("DLSS DLL not found".into()); }
+    let mut report = CommandReport::default();
     for install_location in selected_names {
-        if let Some(ins) = map.get(&install_location) {
-            let src = dir.join("nvngx_dlss.dll");
-            if !src.exists() { return Err("DLSS DLL not found".into()); }
-            let dest = Path::new(&ins.install_location).join("nvngx_dlss.dll");
-            if is_sideloaded(&ins.install_location) {
-                fs::copy(&src, &dest).map_err(|e| e.to_string())?;
-            } else if let Some(ioexe) = get_iobit_unlocker_exe() {
-                // Use IObit via PowerShell to mirror quoting behavior from v2
-                println!("Attempting to delete existing DLSS via IObit: {}", dest.display());
-                let ioexe_ps = ioexe.display().to_string().replace("'", "''");
-                let del_arglist = format!("/Delete \"{}\"", dest.display());
-                let del_arglist_ps = del_arglist.replace("'", "''");
-                let del_ps_cmd = format!(
-                    "Start-Process -FilePath '{}' -ArgumentList '{}' -Wait -PassThru",
-                    ioexe_ps, del_arglist_ps
-                );
-                let out = app_handle.shell().command("powershell.exe")
-                    .args(["-NoProfile", "-ExecutionPolicy", "Bypass", "-Command", &del_ps_cmd])
-                    .output()
-                    .await
-                    .map_err(|e| format!("Failed to run IObit Unlocker: {e}"))?;
-                if !out.status.success() {
-                    let stderr = String::from_utf8_lossy(&out.stderr);
-                    println!("IObit DLSS delete reported non-success for {}: {}", dest.display(), stderr);
-                }
-                println!("Copying DLSS via IObit: {} -> {}", src.display(), dest.display());
-                let copy_arglist = format!("/Copy \"{}\" \"{}\"", src.display(), dest.display());
-                let copy_arglist_ps = copy_arglist.replace("'", "''");
-                let copy_ps_cmd = format!(
-                    "Start-Process -FilePath '{}' -ArgumentList '{}' -Wait -PassThru",
-                    ioexe_ps, copy_arglist_ps
-                );
-                let out = app_handle.shell().command("powershell.exe")
-                    .args(["-NoProfile", "-ExecutionPolicy", "Bypass", "-Command", &copy_ps_cmd])
-                    .output()
-                    .await
-                    .map_err(|e| format!("Failed to run IObit Unlocker: {e}"))?;
-                if !out.status.success() {
-                    let stderr = String::from_utf8_lossy(&out.stderr);
-                    return Err(format!("IObit copy failed for {}: {}", src.display(), stderr));
-                }
-                // Skipping post-copy file verification per user preference
-            } else {
-                return Err("IObit Unlocker not found; cannot update DLSS in WindowsApps".into());
-            }
-        } else {
-            println!("⚠ Skipping unknown selection (no matching installation): {}", install_location);
+        if op.is_cancelled() {
+            report.cancelled(install_location);
+            continue;
         }
+        let Some(ins) = map.get(&install_location) else {
+            report.skipped_unknown(install_location);
+            continue;
+        };
+        let dest = Path::new(&ins.install_location).join("nvngx_dlss.dll");
+        let attempt = install_files(&app_handle, Path::new(&ins.install_location), &[(src.clone(), dest)]).await;
+        journal::record(
+            &JournalEntry::new(&ins.install_location, JournalOperation::Dlss)
+                .source(Some(&src.to_string_lossy()))
+                .files(hash_material_files(std::slice::from_ref(&src)))
+                .backend(attempt.backend)
+                .outcome(&attempt.result),
+        );
+        if let Some(snapshot) = attempt.snapshot {
+            undo::keep(&ins.install_location, JournalOperation::Dlss, snapshot, get_installed_preset(&ins.install_location));
+            op.complete_target(&install_location);
+        }
+        let result = attempt.result;
+        report.record(install_location, result);
     }
-    Ok(())
+    Ok(report)
+}
+
+fn options_file_path(preview: bool) -> PathBuf {
+    let package = if preview { "Microsoft.MinecraftPreview_8wekyb3d8bbwe" } else { "Microsoft.MinecraftUWP_8wekyb3d8bbwe" };
+    local_app_data().join("Packages").join(package).join(r"LocalState\games\com.mojang\minecraftpe\options.txt")
 }
 
 fn update_options_file(path: &Path) -> Result<(), String> {
+    let _guard = OPTIONS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
     if !path.exists() { return Err(format!("Options file not found: {}", path.display())); }
     let mut content = fs::read_to_string(path).map_err(|e| e.to_string())?;
     if content.contains("show_advanced_video_settings:0") {
@@ -963,23 +1596,59 @@ fn update_options_file(path: &Path) -> Result<(), String> {
 }
 
 #[tauri::command]
-async fn update_options_for_selected(app_handle: tauri::AppHandle, selected_names: Vec<String>) -> Result<(), String> {
+async fn update_options_for_selected(app_handle: tauri::AppHandle, selected_names: Vec<String>, dry_run: Option<bool>) -> Result<CommandReport, String> {
+    if !dry_run.unwrap_or(false) {
+        return jobs::run(&app_handle, JobRequest::UpdateOptions { selected_names }).await;
+    }
+    let all = list_installations(app_handle.clone()).await?;
+    // Map by InstallLocation because the UI sends InstallLocation values
+    let map: std::collections::HashMap<_, _> = all
+        .into_iter()
+        .map(|i| (i.install_location.clone(), i))
+        .collect();
+    let mut plan = ActionPlan::new("update_options");
+    for install_location in &selected_names {
+        let Some(ins) = map.get(install_location) else {
+            plan.skipped.push(install_location.clone());
+            continue;
+        };
+        let options = options_file_path(ins.preview);
+        plan.targets.push(plan::TargetPlan {
+            install_location: ins.install_location.clone(),
+            backend: None,
+            changes: vec![plan::PlannedFileChange { path: options.to_string_lossy().to_string(), action: plan::FileAction::Modify }],
+            tracking: TrackingChange::Unchanged,
+            current_preset: None,
+            warnings: Vec::new(),
+        });
+    }
+    Ok(CommandReport::planned(plan))
+}
+
+async fn update_options_job(app_handle: tauri::AppHandle, selected_names: Vec<String>) -> Result<CommandReport, String> {
     let all = list_installations(app_handle.clone()).await?;
     // Map by InstallLocation because the UI sends InstallLocation values
     let map: std::collections::HashMap<_, _> = all
         .into_iter()
         .map(|i| (i.install_location.clone(), i))
         .collect();
-    let com_mojang = local_app_data().join(r"Packages\Microsoft.MinecraftUWP_8wekyb3d8bbwe\LocalState\games\com.mojang\minecraftpe\options.txt");
-    let preview_com_mojang = local_app_data().join(r"Packages\Microsoft.MinecraftPreview_8wekyb3d8bbwe\LocalState\games\com.mojang\minecraftpe\options.txt");
+    let mut report = CommandReport::default();
     for install_location in selected_names {
-        if let Some(ins) = map.get(&install_location) {
-            if ins.preview { update_options_file(&preview_com_mojang)?; } else { update_options_file(&com_mojang)?; }
-        } else {
-            println!("⚠ Skipping unknown selection (no matching installation): {}", install_location);
-        }
+        let Some(ins) = map.get(&install_location) else {
+            report.skipped_unknown(install_location);
+            continue;
+        };
+        let options = options_file_path(ins.preview);
+        let result = update_options_file(&options).map(|_| Vec::new());
+        journal::record(
+            &JournalEntry::new(&ins.install_location, JournalOperation::Options)
+                .source(Some(&options.to_string_lossy()))
+                .backend(Some("direct"))
+                .outcome(&result),
+        );
+        report.record(install_location, result);
     }
-    Ok(())
+    Ok(report)
 }
 
 #[tauri::command]
@@ -1227,6 +1896,47 @@ fn set_iobit_path(path: String) -> Result<String, String> {
     Ok(format!("IObit Unlocker path set to: {}", path))
 }
 
+#[tauri::command]
+fn get_installer_settings() -> Result<InstallerSettings, String> {
+    Ok(settings::load_settings().redacted())
+}
+
+#[tauri::command]
+fn save_installer_settings(settings: InstallerSettings) -> Result<(), String> {
+    let settings = settings.with_stored_password(&settings::load_settings());
+    // Reject settings that would leave every request failing
+    http::build_client(&settings)?;
+    for publisher in &settings.trusted_publishers {
+        signing::parse_public_key(&publisher.public_key)?;
+    }
+    settings::save_settings(&settings)?;
+    http::reset_http_client();
+    Ok(())
+}
+
+#[tauri::command]
+fn list_trusted_publishers() -> Result<Vec<settings::TrustedPublisher>, String> {
+    Ok(settings::load_settings().trusted_publishers)
+}
+
+// Trusts packs signed with `public_key` (base64 Ed25519), replacing any name it had
+#[tauri::command]
+fn add_trusted_publisher(name: String, public_key: String) -> Result<(), String> {
+    signing::parse_public_key(&public_key)?;
+    let public_key = public_key.trim().to_string();
+    let mut settings = settings::load_settings();
+    settings.trusted_publishers.retain(|p| p.public_key != public_key);
+    settings.trusted_publishers.push(settings::TrustedPublisher { name, public_key });
+    settings::save_settings(&settings)
+}
+
+#[tauri::command]
+fn remove_trusted_publisher(public_key: String) -> Result<(), String> {
+    let mut settings = settings::load_settings();
+    settings.trusted_publishers.retain(|p| p.public_key != public_key.trim());
+    settings::save_settings(&settings)
+}
+
 #[tauri::command]
 fn handle_file_drop(_paths: Vec<String>) -> Result<(), String> {
     // This command will be called from the frontend when files are dropped
@@ -1267,15 +1977,15 @@ fn handle_deep_link(url: String) -> Result<ProtocolData, String> {
 }
 
 #[tauri::command]
-async fn download_preset_by_uuid(app_handle: tauri::AppHandle, uuid: String, selected_names: Vec<String>) -> Result<(), String> {
+async fn download_preset_by_uuid(app_handle: tauri::AppHandle, uuid: String, selected_names: Vec<String>) -> Result<CommandReport, String> {
     // Get preset info from API
     let url = format!("https://bedrock.graphics/api/preset/{}", uuid);
-    let client = Client::new();
+    let client = http_client()?;
     let response = client.get(&url).send().await.map_err(|e| e.to_string())?;
     let preset: PackInfo = response.json().await.map_err(|e| e.to_string())?;
     
     // Use existing download and install logic
-    download_and_install_pack(app_handle, preset.uuid, selected_names).await
+    download_and_install_pack(app_handle, preset.uuid, selected_names, None, None).await
 }
 
 #[tauri::command]
@@ -1287,154 +1997,170 @@ fn get_brtx_dir() -> Result<String, String> {
 }
 
 
-#[tauri::command]
-async fn download_creator_settings(
-    app_handle: tauri::AppHandle, 
-    settings_hash: String, 
-    selected_names: Vec<String>,
-    preset_name: Option<String>,
-    uuid: Option<String>
-) -> Result<(), String> {
+// The three material.bin files of a creator settings build, verified against any hashes the caller knows
+fn creator_downloads(settings_hash: &str, material_hashes: Option<HashMap<String, String>>) -> (String, Vec<MaterialDownload>) {
     let base_url = format!("https://bedrock.graphics/build/{}", settings_hash);
-    let dir = brtx_dir().join("creator").join(&settings_hash);
-    ensure_dir(&dir).map_err(|e| e.to_string())?;
-    
-    let client = Client::new();
-    
-    // Download the three material.bin files directly
-    let stub_url = format!("{}/{}", base_url, "stubs/RTXStub.material.bin");
-    let stub_path = dir.join("RTXStub.material.bin");
-    download_to_file_with_cache(&client, &stub_url, &stub_path).await?;
-    let tone_url = format!("{}/{}", base_url, "RTXPostFX.Tonemapping.material.bin");
-    let tone_path = dir.join("RTXPostFX.Tonemapping.material.bin");
-    download_to_file_with_cache(&client, &tone_url, &tone_path).await?;
-    let bloom_url = format!("{}/{}", base_url, "RTXPostFX.Bloom.material.bin");
-    let bloom_path = dir.join("RTXPostFX.Bloom.material.bin");
-    download_to_file_with_cache(&client, &bloom_url, &bloom_path).await?;
-    
-    // Install the downloaded materials
-    let materials = vec![
-        dir.join("RTXStub.material.bin"),
-        dir.join("RTXPostFX.Tonemapping.material.bin"),
-        dir.join("RTXPostFX.Bloom.material.bin"),
-    ];
-    
-    let all = list_installations(app_handle.clone()).await?;
-    let map: std::collections::HashMap<_, _> = all
-        .into_iter()
-        .map(|i| (i.install_location.clone(), i))
-        .collect();
-    
+    let dir = brtx_dir().join("creator").join(settings_hash);
+    let expected = material_hashes.unwrap_or_default();
+    let downloads = [
+        "stubs/RTXStub.material.bin",
+        "RTXPostFX.Tonemapping.material.bin",
+        "RTXPostFX.Bloom.material.bin",
+    ]
+    .iter()
+    .map(|rel| {
+        let file_name = rel.rsplit('/').next().unwrap_or(rel);
+        MaterialDownload {
+            url: format!("{}/{}", base_url, rel),
+            path: dir.join(file_name),
+            sha256: expected.get(file_name).cloned(),
+        }
+    })
+    .collect();
+    (base_url, downloads)
+}
+
+// A pack made in the creator tab, recorded under this name and uuid once installed
+fn creator_pack(name: String, uuid: String) -> PackInfo {
+    PackInfo {
+        name,
+        uuid,
+        stub: String::new(),
+        tonemapping: String::new(),
+        bloom: String::new(),
+        stub_sha256: None,
+        tonemapping_sha256: None,
+        bloom_sha256: None,
+    }
+}
+
+fn creator_settings_pack(settings_hash: &str, preset_name: Option<String>, uuid: Option<String>) -> PackInfo {
     // Use provided name or fallback to hash-based name
-    let display_name = preset_name.unwrap_or_else(|| {
-        let short_hash = settings_hash.get(0..8).unwrap_or(&settings_hash);
+    let name = preset_name.unwrap_or_else(|| {
+        let short_hash = settings_hash.get(0..8).unwrap_or(settings_hash);
         format!("Creator Settings ({})", short_hash)
     });
-    
     // Use provided UUID or generate one from hash
-    let creator_uuid = uuid.unwrap_or_else(|| format!("creator-{}", settings_hash));
-        
+    creator_pack(name, uuid.unwrap_or_else(|| format!("creator-{}", settings_hash)))
+}
+
+async fn installations_by_location(app_handle: &tauri::AppHandle) -> Result<HashMap<String, Installation>, String> {
+    // Keyed by InstallLocation because the UI sends InstallLocation values
+    let all = list_installations(app_handle.clone()).await?;
+    Ok(all.into_iter().map(|i| (i.install_location.clone(), i)).collect())
+}
+
+// Plans installing creator-made materials into each selected installation
+async fn plan_creator_install(app_handle: &tauri::AppHandle, operation: &str, downloads: Vec<PlannedDownload>, material_names: &[String], selected_names: &[String], pack: PackInfo) -> Result<CommandReport, String> {
+    let map = installations_by_location(app_handle).await?;
+    let mut plan = ActionPlan::new(operation);
+    plan.downloads = downloads;
+    let tracking = TrackingChange::Set { uuid: pack.uuid, name: pack.name };
+    plan::plan_material_targets(&mut plan, material_names, selected_names, &map, tracking);
+    Ok(CommandReport::planned(plan))
+}
+
+// Installs creator-made materials into each selected installation and marks the
+// installed preset as creator-made
+async fn install_creator_materials(app_handle: &tauri::AppHandle, selected_names: Vec<String>, materials: &[PathBuf], pack: &PackInfo, source: &str, known_hashes: &DownloadHashes) -> Result<CommandReport, String> {
+    let map = installations_by_location(app_handle).await?;
+    let mut report = CommandReport::default();
     for install_location in selected_names {
-        if let Some(ins) = map.get(&install_location) {
-            let creator_pack = PackInfo {
-                name: display_name.clone(),
-                uuid: creator_uuid.clone(),
-                stub: String::new(),
-                tonemapping: String::new(),
-                bloom: String::new(),
-            };
-            
-            // Install materials using existing infrastructure
-            copy_shader_files_async(&app_handle, &ins.install_location, &materials, &creator_pack).await?;
-            
-            // Override the saved preset to mark it as creator-made
-            let creator_preset = InstalledPreset {
-                uuid: creator_uuid.clone(),
-                name: display_name.clone(),
-                installed_at: chrono::Utc::now().to_rfc3339(),
-                is_creator: Some(true),
-            };
-            
-            if let Err(e) = save_installed_preset(&ins.install_location, &creator_preset) {
-                println!("⚠ Failed to save creator preset tracking: {}", e);
+        let Some(ins) = map.get(&install_location) else {
+            report.skipped_unknown(install_location);
+            continue;
+        };
+        let result = copy_shader_files_async(app_handle, &ins.install_location, materials, pack, JournalOperation::Install, Some(source), known_hashes).await;
+        if result.is_ok() {
+            if let Some(mut preset) = get_installed_preset(&ins.install_location) {
+                preset.is_creator = Some(true);
+                if let Err(e) = save_installed_preset(&ins.install_location, &preset) {
+                    println!("⚠ Failed to save creator preset tracking: {}", e);
+                }
             }
-        } else {
-            println!("⚠ Skipping unknown selection (no matching installation): {}", install_location);
         }
+        report.record(install_location, result);
     }
-    
-    Ok(())
+    Ok(report)
 }
 
 #[tauri::command]
-async fn install_uploaded_materials(
-    app_handle: tauri::AppHandle,
+async fn download_creator_settings(
+    app_handle: tauri::AppHandle, 
+    settings_hash: String, 
     selected_names: Vec<String>,
-    preset_name: Option<String>
-) -> Result<(), String> {
-    let uploaded_dir = brtx_dir().join("creator").join("uploaded");
-    
-    // Get all uploaded material files
-    let materials: Vec<PathBuf> = if uploaded_dir.exists() {
-        fs::read_dir(&uploaded_dir)
-            .map_err(|e| e.to_string())?
-            .filter_map(|entry| {
-                let entry = entry.ok()?;
-                let path = entry.path();
-                if path.is_file() && path.extension()?.to_str()? == "bin" {
-                    Some(path)
-                } else {
-                    None
-                }
-            })
-            .collect()
-    } else {
+    preset_name: Option<String>,
+    uuid: Option<String>,
+    material_hashes: Option<HashMap<String, String>>,
+    dry_run: Option<bool>
+) -> Result<CommandReport, String> {
+    if !dry_run.unwrap_or(false) {
+        return jobs::run(&app_handle, JobRequest::InstallCreatorSettings { settings_hash, selected_names, preset_name, uuid, material_hashes }).await;
+    }
+    let (_, downloads) = creator_downloads(&settings_hash, material_hashes);
+    let names: Vec<String> = downloads.iter().filter_map(|d| d.path.file_name().map(|n| n.to_string_lossy().to_string())).collect();
+    let pack = creator_settings_pack(&settings_hash, preset_name, uuid);
+    plan_creator_install(&app_handle, "install_creator_settings", plan::plan_downloads(&downloads).await, &names, &selected_names, pack).await
+}
+
+async fn install_creator_settings_job(app_handle: tauri::AppHandle, settings_hash: String, selected_names: Vec<String>, preset_name: Option<String>, uuid: Option<String>, material_hashes: Option<HashMap<String, String>>) -> Result<CommandReport, String> {
+    let (base_url, downloads) = creator_downloads(&settings_hash, material_hashes);
+    let pack = creator_settings_pack(&settings_hash, preset_name, uuid);
+    ensure_dir(&brtx_dir().join("creator").join(&settings_hash)).map_err(|e| e.to_string())?;
+    // Download the three material.bin files concurrently
+    let hashes = download_all_with_cache(&http_client()?, &downloads).await?;
+    let materials: Vec<PathBuf> = downloads.into_iter().map(|d| d.path).collect();
+    install_creator_materials(&app_handle, selected_names, &materials, &pack, &base_url, &hashes).await
+}
+
+// Material files uploaded through the creator tab
+fn uploaded_materials(uploaded_dir: &Path) -> Result<Vec<PathBuf>, String> {
+    if !uploaded_dir.exists() {
         return Err("No uploaded material files found".to_string());
-    };
-    
+    }
+    let materials: Vec<PathBuf> = fs::read_dir(uploaded_dir)
+        .map_err(|e| e.to_string())?
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let path = entry.path();
+            if path.is_file() && path.extension()?.to_str()? == "bin" {
+                Some(path)
+            } else {
+                None
+            }
+        })
+        .collect();
     if materials.is_empty() {
         return Err("No material files to install".to_string());
     }
-    
-    let all = list_installations(app_handle.clone()).await?;
-    let map: std::collections::HashMap<_, _> = all
-        .into_iter()
-        .map(|i| (i.install_location.clone(), i))
-        .collect();
-    
-    let display_name = preset_name.unwrap_or_else(|| "Uploaded Materials".to_string());
-    let material_uuid = format!("materials-{}", chrono::Utc::now().timestamp());
-    
-    for install_location in selected_names {
-        if let Some(ins) = map.get(&install_location) {
-            let material_pack = PackInfo {
-                name: display_name.clone(),
-                uuid: material_uuid.clone(),
-                stub: String::new(),
-                tonemapping: String::new(),
-                bloom: String::new(),
-            };
-            
-            // Install materials using existing infrastructure
-            copy_shader_files_async(&app_handle, &ins.install_location, &materials, &material_pack).await?;
-            
-            // Save as creator preset
-            let creator_preset = InstalledPreset {
-                uuid: material_uuid.clone(),
-                name: display_name.clone(),
-                installed_at: chrono::Utc::now().to_rfc3339(),
-                is_creator: Some(true),
-            };
-            
-            if let Err(e) = save_installed_preset(&ins.install_location, &creator_preset) {
-                println!("⚠ Failed to save material preset tracking: {}", e);
-            }
-        } else {
-            println!("⚠ Skipping unknown selection (no matching installation): {}", install_location);
-        }
+    Ok(materials)
+}
+
+fn uploaded_pack(preset_name: Option<String>) -> PackInfo {
+    let name = preset_name.unwrap_or_else(|| "Uploaded Materials".to_string());
+    creator_pack(name, format!("materials-{}", chrono::Utc::now().timestamp()))
+}
+
+#[tauri::command]
+async fn install_uploaded_materials(
+    app_handle: tauri::AppHandle,
+    selected_names: Vec<String>,
+    preset_name: Option<String>,
+    dry_run: Option<bool>
+) -> Result<CommandReport, String> {
+    if !dry_run.unwrap_or(false) {
+        return jobs::run(&app_handle, JobRequest::InstallUploadedMaterials { selected_names, preset_name }).await;
     }
-    
-    Ok(())
+    let materials = uploaded_materials(&brtx_dir().join("creator").join("uploaded"))?;
+    let downloads = materials.iter().map(|m| plan::plan_local_file(m)).collect();
+    plan_creator_install(&app_handle, "install_uploaded_materials", downloads, &material_file_names(&materials), &selected_names, uploaded_pack(preset_name)).await
+}
+
+async fn install_uploaded_materials_job(app_handle: tauri::AppHandle, selected_names: Vec<String>, preset_name: Option<String>) -> Result<CommandReport, String> {
+    let uploaded_dir = brtx_dir().join("creator").join("uploaded");
+    let materials = uploaded_materials(&uploaded_dir)?;
+    let pack = uploaded_pack(preset_name);
+    install_creator_materials(&app_handle, selected_names, &materials, &pack, &uploaded_dir.to_string_lossy(), &DownloadHashes::new()).await
 }
 
 #[tauri::command]
@@ -1451,7 +2177,7 @@ async fn upload_material_file(source_path: String) -> Result<String, String> {
     
     // Copy file to target directory
     let target_path = target_dir.join(filename);
-    fs::copy(&source, &target_path).map_err(|e| e.to_string())?;
+    fs::copy(source, &target_path).map_err(|e| e.to_string())?;
     
     Ok(filename.to_string())
 }
@@ -1525,96 +2251,203 @@ fn get_iobit_path() -> Result<Option<String>, String> {
 }
 
 #[tauri::command]
-async fn uninstall_rtx(app_handle: tauri::AppHandle, selected_names: Vec<String>) -> Result<(), String> {
+async fn uninstall_rtx(app_handle: tauri::AppHandle, selected_names: Vec<String>, dry_run: Option<bool>) -> Result<CommandReport, String> {
+    if !dry_run.unwrap_or(false) {
+        return jobs::run(&app_handle, JobRequest::Uninstall { selected_names }).await;
+    }
     // Download original material.bin files from the uninstall API endpoints
     let dir = brtx_dir().join("uninstall");
-    ensure_dir(&dir).map_err(|e| e.to_string())?;
-    let client = Client::new();
-    
-    // Download original files with caching
-    let stub_path = dir.join("RTXStub.material.bin");
-    download_to_file_with_cache(&client, "https://bedrock.graphics/api/uninstall/rtxstub", &stub_path).await?;
-    let tone_path = dir.join("RTXPostFX.Tonemapping.material.bin");
-    download_to_file_with_cache(&client, "https://bedrock.graphics/api/uninstall/rtxpostfx", &tone_path).await?;
-    let bloom_path = dir.join("RTXPostFX.Bloom.material.bin");
-    download_to_file_with_cache(&client, "https://bedrock.graphics/api/uninstall/bloom", &bloom_path).await?;
-
-    let materials = vec![
-        stub_path.clone(),
-        tone_path.clone(),
-        bloom_path.clone(),
-    ];
+    let downloads = uninstall_downloads(&dir);
+    
+    let all = list_installations(app_handle.clone()).await?;
+    let map: std::collections::HashMap<_, _> = all
+        .into_iter()
+        .map(|i| (i.install_location.clone(), i))
+        .collect();
+
+    let mut plan = ActionPlan::new("uninstall");
+    plan.downloads = plan::plan_downloads(&downloads).await;
+    let names: Vec<String> = downloads.iter().filter_map(|d| d.path.file_name().map(|n| n.to_string_lossy().to_string())).collect();
+    plan::plan_material_targets(&mut plan, &names, &selected_names, &map, TrackingChange::Remove);
+    Ok(CommandReport::planned(plan))
+}
+
+async fn uninstall_job(app_handle: tauri::AppHandle, selected_names: Vec<String>) -> Result<CommandReport, String> {
+    // Download original material.bin files from the uninstall API endpoints
+    let dir = brtx_dir().join("uninstall");
+    let downloads = uninstall_downloads(&dir);
     
     let all = list_installations(app_handle.clone()).await?;
     let map: std::collections::HashMap<_, _> = all
         .into_iter()
         .map(|i| (i.install_location.clone(), i))
         .collect();
+
+    ensure_dir(&dir).map_err(|e| e.to_string())?;
+    let client = http_client()?;
+    
+    // Download original files concurrently with caching
+    let hashes = download_all_with_cache(&client, &downloads).await?;
+
+    let materials: Vec<PathBuf> = downloads.into_iter().map(|d| d.path).collect();
     
+    let mut report = CommandReport::default();
     for install_location in selected_names {
-        if let Some(ins) = map.get(&install_location) {
-            // Create a dummy pack for uninstall
-            let uninstall_pack = PackInfo {
-                name: "Original Files".to_string(),
-                uuid: "uninstall-original".to_string(),
-                stub: String::new(),
-                tonemapping: String::new(),
-                bloom: String::new(),
-            };
-            copy_shader_files_async(&app_handle, &ins.install_location, &materials, &uninstall_pack).await?;
-            
+        let Some(ins) = map.get(&install_location) else {
+            report.skipped_unknown(install_location);
+            continue;
+        };
+        // Create a dummy pack for uninstall
+        let uninstall_pack = PackInfo {
+            name: "Original Files".to_string(),
+            uuid: "uninstall-original".to_string(),
+            stub: String::new(),
+            tonemapping: String::new(),
+            bloom: String::new(),
+            stub_sha256: None,
+            tonemapping_sha256: None,
+            bloom_sha256: None,
+        };
+        let result = copy_shader_files_async(&app_handle, &ins.install_location, &materials, &uninstall_pack, JournalOperation::Uninstall, None, &hashes).await;
+        if result.is_ok() {
             // Remove the installed preset tracking for this installation
-            let presets_file = brtx_dir().join("installed_presets.json");
-            if let Some(mut installations) = read_json_file::<HashMap<String, InstalledPreset>>(&presets_file) {
-                installations.remove(&install_location);
-                let _ = write_json_file(&presets_file, &installations);
+            remove_installed_preset(&install_location);
+        }
+        report.record(install_location, result);
+    }
+    Ok(report)
+}
+
+// Puts back the files and preset record from before the installation's last change.
+// Repeating it steps further back, up to the number of undo points kept.
+#[tauri::command]
+async fn undo_last(app_handle: tauri::AppHandle, install_location: String) -> Result<CommandReport, String> {
+    jobs::run(&app_handle, JobRequest::Undo { install_location }).await
+}
+
+async fn undo_job(app_handle: tauri::AppHandle, install_location: String) -> Result<CommandReport, String> {
+    let point = undo::latest(&install_location)?.ok_or_else(|| format!("Nothing to undo for {}", install_location))?;
+    let saved = point.saved_pairs();
+    let attempt = replace_files(&app_handle, Path::new(&install_location), &point.targets(), &saved).await;
+
+    let mut entry = JournalEntry::new(&install_location, JournalOperation::Undo)
+        .files(hash_material_files(&saved.iter().map(|(s, _)| s.clone()).collect::<Vec<_>>()))
+        .backend(attempt.backend)
+        .outcome(&attempt.result);
+    if let Some(preset) = &point.previous_preset {
+        entry = entry.preset(&preset.uuid, &preset.name);
+    }
+    journal::record(&entry);
+
+    if attempt.result.is_ok() {
+        match &point.previous_preset {
+            Some(preset) => {
+                if let Err(e) = save_installed_preset(&install_location, preset) {
+                    println!("⚠ Failed to restore preset tracking: {}", e);
+                }
             }
-        } else {
-            println!("⚠ Skipping unknown selection (no matching installation): {}", install_location);
+            None => remove_installed_preset(&install_location),
         }
+        point.discard();
     }
-    Ok(())
+    let mut report = CommandReport::default();
+    report.record(install_location, attempt.result);
+    Ok(report)
+}
+
+// The initial material backup of an installation and the files it holds
+fn initial_backup(ins: &Installation) -> (PathBuf, Vec<PathBuf>) {
+    let backup = brtx_dir().join("backup").join(&ins.friendly_name);
+    let materials = ["RTXStub.material.bin", "RTXPostFX.Tonemapping.material.bin", "RTXPostFX.Bloom.material.bin"]
+        .iter()
+        .map(|name| backup.join(name))
+        .filter(|p| p.exists())
+        .collect();
+    (backup, materials)
 }
 
 #[tauri::command]
-async fn uninstall_package(app_handle: tauri::AppHandle, restore_initial: bool) -> Result<(), String> {
-    if restore_initial {
-        let all = list_installations(app_handle.clone()).await?;
-        for ins in all {
-            let backup = brtx_dir().join("backup").join(&ins.friendly_name);
-            if backup.exists() {
-                let materials = vec![
-                    backup.join("RTXStub.material.bin"),
-                    backup.join("RTXPostFX.Tonemapping.material.bin"),
-                    backup.join("RTXPostFX.Bloom.material.bin"),
-                ];
-                let existing: Vec<PathBuf> = materials.into_iter().filter(|p| p.exists()).collect();
-                if !existing.is_empty() { 
-                    let dummy_pack = PackInfo {
-                        name: "Backup Restore".to_string(),
-                        uuid: "backup-restore".to_string(),
-                        stub: String::new(),
-                        tonemapping: String::new(),
-                        bloom: String::new(),
-                    };
-                    copy_shader_files_async(&app_handle, &ins.install_location, &existing, &dummy_pack).await?; 
+async fn uninstall_package(app_handle: tauri::AppHandle, restore_initial: bool, operation_id: Option<String>, dry_run: Option<bool>) -> Result<CommandReport, String> {
+    let all = list_installations(app_handle.clone()).await?;
+    if dry_run.unwrap_or(false) {
+        let mut plan = ActionPlan::new("uninstall_package");
+        if restore_initial {
+            for ins in &all {
+                let (_, materials) = initial_backup(ins);
+                if materials.is_empty() {
+                    continue;
                 }
+                plan.downloads.extend(materials.iter().map(|m| plan::plan_local_file(m)));
+                let map = HashMap::from([(ins.install_location.clone(), ins.clone())]);
+                let planned = plan.targets.len();
+                plan::plan_material_targets(&mut plan, &material_file_names(&materials), std::slice::from_ref(&ins.install_location), &map, TrackingChange::Remove);
+                plan::warn_material_versions(&mut plan.targets[planned..], &material::file_versions(&materials));
             }
         }
+        plan.removed = uninstall_removals().iter().map(|p| p.to_string_lossy().to_string()).collect();
+        return Ok(CommandReport::planned(plan));
     }
-    
-    // Clear installed presets tracking
-    let presets_file = brtx_dir().join("installed_presets.json");
-    if presets_file.exists() {
-        let _ = fs::remove_file(&presets_file);
+    let selected_names = all.into_iter().map(|i| i.install_location).collect();
+    jobs::run(&app_handle, JobRequest::UninstallPackage { restore_initial, selected_names, operation_id }).await
+}
+
+async fn uninstall_package_job(app_handle: tauri::AppHandle, restore_initial: bool, op: &Operation) -> Result<CommandReport, String> {
+    let mut report = CommandReport::default();
+    if restore_initial {
+        for ins in list_installations(app_handle.clone()).await? {
+            if op.is_cancelled() {
+                report.cancelled(ins.install_location);
+                continue;
+            }
+            // Installations without an initial backup have nothing to restore
+            let (backup, materials) = initial_backup(&ins);
+            if materials.is_empty() {
+                continue;
+            }
+            let pack = creator_pack("Backup Restore".to_string(), "backup-restore".to_string());
+            let result = copy_shader_files_async(&app_handle, &ins.install_location, &materials, &pack, JournalOperation::Restore, Some(&backup.to_string_lossy()), &DownloadHashes::new()).await;
+            if result.is_ok() {
+                op.complete_target(&ins.install_location);
+            }
+            report.record(ins.install_location, result);
+        }
     }
-    
-    let _ = fs::remove_dir_all(brtx_dir());
-    Ok(())
+
+    // Keep the installer data, backups included, so a failed restore can be retried
+    if !report.succeeded() {
+        return Ok(report);
+    }
+    for path in uninstall_removals() {
+        let _ = if path.is_dir() { fs::remove_dir_all(&path) } else { fs::remove_file(&path) };
+    }
+    Ok(report)
+}
+
+// Installer data uninstall_package keeps: the journal, which records the uninstall itself,
+// the undo points so undo_last can still bring the replaced files back, and the settings
+const KEPT_ON_UNINSTALL: &[&str] = &["journal", "undo", "settings.json"];
+
+// The entries of brtx_dir() that uninstall_package deletes
+fn uninstall_removals() -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(brtx_dir()) else {
+        return Vec::new();
+    };
+    let mut removals: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| !path.file_name().is_some_and(|name| KEPT_ON_UNINSTALL.iter().any(|kept| name == *kept)))
+        .collect();
+    removals.sort();
+    removals
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // Elevated file operations run in a hidden helper mode of this same binary
+    if let Some(code) = helper::run_from_args(&std::env::args().collect::<Vec<_>>()) {
+        std::process::exit(code);
+    }
+
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
@@ -1640,9 +2473,16 @@ pub fn run() {
             get_api_packs,
             list_presets,
             download_and_install_pack,
+            prefetch_presets,
             install_from_rtpack,
+            import_material_source,
+            inspect_rtpack,
+            detect_material_source,
             install_materials,
             backup_selected,
+            export_rtpack,
+            diff_material_sources,
+            read_material_info,
             install_dlss_for_selected,
             update_options_for_selected,
             register_rtpack_extension,
@@ -1653,12 +2493,26 @@ pub fn run() {
             save_minecraft_options,
             set_iobit_path,
             get_iobit_path,
+            get_installer_settings,
+            save_installer_settings,
+            list_trusted_publishers,
+            add_trusted_publisher,
+            remove_trusted_publisher,
             open_iobit_file_dialog,
             handle_file_drop,
             uninstall_package,
             uninstall_rtx,
+            undo_last,
             clear_cache,
             get_cache_info,
+            cancel_operation,
+            enqueue_job,
+            list_jobs,
+            get_job_status,
+            get_job_history,
+            get_install_journal,
+            export_offline_bundle,
+            import_offline_bundle,
             handle_deep_link,
             download_preset_by_uuid,
             download_creator_settings,
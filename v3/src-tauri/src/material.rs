@@ -0,0 +1,321 @@
+// Parser for Bedrock's material.bin container (RenderDragon compiled material
+// definitions). All integers are little-endian and strings are prefixed with their u32
+// length. The header and the closing magic are required; the sampler, property and pass
+// tables change between game versions, so they are read on a best-effort basis.
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+const MAGIC: u64 = 0x0A11_DA1A;
+const DEFINITION: &str = "RenderDragon.CompiledMaterialDefinition";
+// Bytes read for a header-only parse; far above any real name and parent
+pub(crate) const HEADER_LIMIT: u64 = 64 * 1024;
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub(crate) struct MaterialInfo {
+    pub version: u64,
+    // "none", "simple_passphrase" or "key_pair"; anything else is rejected
+    pub encryption: String,
+    // Only readable when the definition is not encrypted
+    pub name: Option<String>,
+    pub parent: Option<String>,
+    // None when the pass table could not be read
+    pub passes: Option<Vec<String>>,
+    // Shader platforms the passes were compiled for, e.g. "Direct3D_SM65"
+    pub platforms: Vec<String>,
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self.pos.checked_add(len).filter(|end| *end <= self.data.len()).ok_or("Material file is truncated")?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn bool(&mut self) -> Result<bool, String> {
+        Ok(self.u8()? != 0)
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_le_bytes(self.bytes(2)?.try_into().unwrap_or_default()))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap_or_default()))
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.bytes(8)?.try_into().unwrap_or_default()))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let len = self.u32()? as usize;
+        String::from_utf8(self.bytes(len)?.to_vec()).map_err(|_| "Material file has an invalid string".to_string())
+    }
+
+    fn skip_string(&mut self) -> Result<(), String> {
+        let len = self.u32()? as usize;
+        self.bytes(len).map(|_| ())
+    }
+
+    // An optional value: a bool flag followed by the value when set
+    fn skip_if_set(&mut self, read: impl FnOnce(&mut Self) -> Result<(), String>) -> Result<(), String> {
+        if self.bool()? {
+            read(self)?;
+        }
+        Ok(())
+    }
+}
+
+fn skip_sampler(r: &mut Reader) -> Result<(), String> {
+    r.skip_string()?;
+    // Register, access, precision, unordered access and sampler type
+    r.bytes(2 + 1 + 1 + 1 + 1)?;
+    r.skip_string()?; // texture format
+    r.bytes(4 + 1)?;
+    r.skip_if_set(|r| r.bytes(1).map(|_| ()))?; // sampler state
+    r.skip_if_set(Reader::skip_string)?; // default texture
+    r.skip_if_set(Reader::skip_string)?;
+    r.skip_if_set(|r| r.skip_string().and_then(|_| r.bytes(4).map(|_| ())))?; // custom type info
+    Ok(())
+}
+
+fn skip_property(r: &mut Reader) -> Result<(), String> {
+    r.skip_string()?;
+    let kind = r.u16()?;
+    r.u32()?;
+    // Default values for vec4, mat3 and mat4 properties; external ones have none
+    let data_len = match kind {
+        0 => 16,
+        1 => 36,
+        2 => 64,
+        3 => return Ok(()),
+        other => return Err(format!("Unknown material property type {}", other)),
+    };
+    r.skip_if_set(|r| r.bytes(data_len).map(|_| ()))
+}
+
+fn skip_flags(r: &mut Reader) -> Result<(), String> {
+    for _ in 0..r.u16()? {
+        r.skip_string()?;
+        r.skip_string()?;
+    }
+    Ok(())
+}
+
+// Reads one pass and adds the platforms of its shaders to `platforms`
+fn read_pass(r: &mut Reader, platforms: &mut Vec<String>) -> Result<String, String> {
+    let name = r.string()?;
+    r.skip_string()?; // supported platforms bitset
+    r.skip_string()?; // fallback pass
+    r.skip_if_set(|r| r.u16().map(|_| ()))?; // default blend mode
+    skip_flags(r)?;
+    for _ in 0..r.u16()? {
+        r.bool()?; // is supported
+        skip_flags(r)?;
+        for _ in 0..r.u16()? {
+            r.skip_string()?; // stage
+            let platform = r.string()?;
+            r.bytes(2)?;
+            for _ in 0..r.u16()? {
+                r.skip_string()?;
+                r.bytes(1 + 2 + 1)?; // type, attribute, per instance
+                r.skip_if_set(|r| r.bytes(1).map(|_| ()))?; // precision
+                r.skip_if_set(|r| r.bytes(1).map(|_| ()))?; // interpolation
+            }
+            r.u64()?; // hash
+            let len = r.u32()? as usize;
+            r.bytes(len)?; // compiled shader
+            if !platforms.contains(&platform) {
+                platforms.push(platform);
+            }
+        }
+    }
+    Ok(name)
+}
+
+// The pass names and shader platforms, or None if the tables do not read cleanly up to
+// the closing magic
+fn read_passes(r: &mut Reader) -> Option<(Vec<String>, Vec<String>)> {
+    for _ in 0..r.u8().ok()? {
+        skip_sampler(r).ok()?;
+    }
+    for _ in 0..r.u16().ok()? {
+        skip_property(r).ok()?;
+    }
+    let mut passes = Vec::new();
+    let mut platforms = Vec::new();
+    for _ in 0..r.u16().ok()? {
+        passes.push(read_pass(r, &mut platforms).ok()?);
+    }
+    (r.u64().ok()? == MAGIC && r.pos == r.data.len()).then_some((passes, platforms))
+}
+
+// Reads the header: the definition, version, encryption and, when unencrypted, the
+// name and parent
+fn read_header(reader: &mut Reader) -> Result<MaterialInfo, String> {
+    if reader.u64().map_err(|_| "Not a material file")? != MAGIC {
+        return Err("Not a material file".to_string());
+    }
+    if reader.string().ok().as_deref() != Some(DEFINITION) {
+        return Err("Not a compiled material definition".to_string());
+    }
+    let version = reader.u64()?;
+    let encryption = match &reader.bytes(4)?.iter().rev().map(|b| *b as char).collect::<String>()[..] {
+        "NONE" => "none",
+        "SMPL" => "simple_passphrase",
+        "KYPR" => "key_pair",
+        other => return Err(format!("Unknown material encryption: {:?}", other)),
+    };
+    let mut info = MaterialInfo { version, encryption: encryption.to_string(), name: None, parent: None, passes: None, platforms: Vec::new() };
+    if encryption != "none" {
+        return Ok(info);
+    }
+    info.name = Some(reader.string()?);
+    if reader.bool()? {
+        info.parent = Some(reader.string()?);
+    }
+    Ok(info)
+}
+
+// Every unencrypted material ends with the magic again; a cut-off download does not
+pub(crate) fn check_end(info: &MaterialInfo, tail: &[u8]) -> Result<(), String> {
+    if info.encryption == "none" && !tail.ends_with(&MAGIC.to_le_bytes()) {
+        return Err("Material file is truncated".to_string());
+    }
+    Ok(())
+}
+
+pub(crate) fn parse(data: &[u8]) -> Result<MaterialInfo, String> {
+    let mut reader = Reader { data, pos: 0 };
+    let mut info = read_header(&mut reader)?;
+    if info.encryption != "none" {
+        return Ok(info);
+    }
+    if data.len() < reader.pos + 8 {
+        return Err("Material file is truncated".to_string());
+    }
+    check_end(&info, data)?;
+    if let Some((passes, platforms)) = read_passes(&mut reader) {
+        info.passes = Some(passes);
+        info.platforms = platforms;
+    }
+    Ok(info)
+}
+
+// Parses only the header from the first HEADER_LIMIT bytes of `source`. The pass table
+// is not read and truncation is not detected; see check_end.
+pub(crate) fn parse_header(source: impl Read) -> Result<MaterialInfo, String> {
+    let mut data = Vec::new();
+    source.take(HEADER_LIMIT).read_to_end(&mut data).map_err(|e| format!("Failed to read material: {e}"))?;
+    read_header(&mut Reader { data: &data, pos: 0 })
+}
+
+pub(crate) fn parse_file(path: &Path) -> Result<MaterialInfo, String> {
+    let data = std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    parse(&data).map_err(|e| format!("{}: {}", path.file_name().unwrap_or_default().to_string_lossy(), e))
+}
+
+pub(crate) fn parse_file_header(path: &Path) -> Result<MaterialInfo, String> {
+    let file = std::fs::File::open(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    parse_header(file).map_err(|e| format!("{}: {}", path.file_name().unwrap_or_default().to_string_lossy(), e))
+}
+
+// Material version of each file that parses, keyed by file name
+pub(crate) fn file_versions(paths: &[PathBuf]) -> HashMap<String, u64> {
+    paths
+        .iter()
+        .filter_map(|path| Some((path.file_name()?.to_string_lossy().to_string(), parse_file_header(path).ok()?.version)))
+        .collect()
+}
+
+// Set when the material at `dest` has another version than the one replacing it; the
+// game may not load materials built for a different version
+pub(crate) fn version_warning(file_name: &str, version: u64, dest: &Path) -> Option<String> {
+    let current = parse_file_header(dest).ok()?.version;
+    (current != version).then(|| format!("{} is material version {}, the installation has version {}", file_name, version, current))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    fn string(out: &mut Vec<u8>, s: &str) {
+        out.extend((s.len() as u32).to_le_bytes());
+        out.extend(s.as_bytes());
+    }
+
+    // An unencrypted material with no samplers or properties and one pass compiled for
+    // a single platform, laid out as the game writes it
+    pub(crate) fn material(version: u64, name: &str, parent: Option<&str>) -> Vec<u8> {
+        let mut out = MAGIC.to_le_bytes().to_vec();
+        string(&mut out, DEFINITION);
+        out.extend(version.to_le_bytes());
+        out.extend(b"ENON");
+        string(&mut out, name);
+        out.push(parent.is_some() as u8);
+        if let Some(parent) = parent {
+            string(&mut out, parent);
+        }
+        out.push(0);
+        out.extend(0u16.to_le_bytes());
+        out.extend(1u16.to_le_bytes());
+        string(&mut out, "Transparent");
+        string(&mut out, "100000000000000");
+        string(&mut out, "");
+        out.push(0);
+        out.extend(0u16.to_le_bytes());
+        out.extend(1u16.to_le_bytes());
+        out.push(1);
+        out.extend(0u16.to_le_bytes());
+        out.extend(1u16.to_le_bytes());
+        string(&mut out, "Compute");
+        string(&mut out, "Direct3D_SM65");
+        out.extend([4, 3]);
+        out.extend(0u16.to_le_bytes());
+        out.extend(0u64.to_le_bytes());
+        string(&mut out, "DXBC");
+        out.extend(MAGIC.to_le_bytes());
+        out
+    }
+
+    #[test]
+    fn parses_materials() {
+        let parsed = parse(&material(22, "RTXStub", Some("Core/Base"))).unwrap();
+        assert_eq!(parsed, MaterialInfo {
+            version: 22,
+            encryption: "none".to_string(),
+            name: Some("RTXStub".to_string()),
+            parent: Some("Core/Base".to_string()),
+            passes: Some(vec!["Transparent".to_string()]),
+            platforms: vec!["Direct3D_SM65".to_string()],
+        });
+        assert!(parse(b"<html>Not Found</html>").unwrap_err().contains("Not a material"));
+        let full = material(22, "RTXStub", None);
+        assert!(parse(&full[..full.len() - 3]).unwrap_err().contains("truncated"));
+    }
+
+    #[test]
+    fn parses_headers_from_a_bounded_reader() {
+        let full = material(22, "RTXStub", Some("Core/Base"));
+        let header = parse_header(&full[..full.len() - 3]).unwrap();
+        assert_eq!((header.version, header.name.as_deref(), header.passes.is_none()), (22, Some("RTXStub"), true));
+        assert!(check_end(&header, &full[..full.len() - 3]).unwrap_err().contains("truncated"));
+        assert!(check_end(&header, &full).is_ok());
+
+        // A name longer than the limit is never read into memory
+        let huge = material(22, &"x".repeat(HEADER_LIMIT as usize), None);
+        assert!(parse_header(&huge[..]).unwrap_err().contains("truncated"));
+    }
+}
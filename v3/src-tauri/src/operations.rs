@@ -0,0 +1,151 @@
+// Registry of long-running operations that the UI can cancel at a safe point
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, LazyLock, Mutex};
+use tokio::sync::watch;
+
+static OPERATIONS: LazyLock<Mutex<HashMap<String, Arc<Operation>>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+pub(crate) const CANCELLED_ERROR: &str = "Operation cancelled";
+
+#[derive(Serialize, Debug, Clone)]
+pub(crate) struct OperationReport {
+    operation_id: String,
+    cancelled: bool,
+    // Targets the operation finished before it stopped
+    completed: Vec<String>,
+    // Targets that were never touched
+    untouched: Vec<String>,
+}
+
+pub(crate) struct Operation {
+    id: String,
+    targets: Vec<String>,
+    completed: Mutex<Vec<String>>,
+    cancel_tx: watch::Sender<bool>,
+    finished_tx: watch::Sender<bool>,
+}
+
+impl Operation {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    // Safe point: checked before starting work on the next target, which is then
+    // reported as cancelled instead of processed
+    pub fn is_cancelled(&self) -> bool {
+        *self.cancel_tx.borrow()
+    }
+
+    // Runs a future (e.g. a download) that is abandoned as soon as the operation is cancelled
+    pub async fn cancellable<T, F>(&self, fut: F) -> Result<T, String>
+    where
+        F: Future<Output = Result<T, String>>,
+    {
+        let mut cancel_rx = self.cancel_tx.subscribe();
+        tokio::select! {
+            result = fut => result,
+            _ = cancel_rx.wait_for(|cancelled| *cancelled) => Err(CANCELLED_ERROR.to_string()),
+        }
+    }
+
+    pub fn complete_target(&self, target: &str) {
+        if let Ok(mut completed) = self.completed.lock() {
+            completed.push(target.to_string());
+        }
+    }
+
+    fn report(&self) -> OperationReport {
+        let completed = self.completed.lock().map(|c| c.clone()).unwrap_or_default();
+        let untouched = self
+            .targets
+            .iter()
+            .filter(|t| !completed.contains(t))
+            .cloned()
+            .collect();
+        OperationReport {
+            operation_id: self.id.clone(),
+            cancelled: self.is_cancelled(),
+            completed,
+            untouched,
+        }
+    }
+}
+
+// Keeps an operation registered for as long as the command runs
+pub(crate) struct OperationGuard(Arc<Operation>);
+
+impl std::ops::Deref for OperationGuard {
+    type Target = Operation;
+    fn deref(&self) -> &Operation {
+        &self.0
+    }
+}
+
+impl Drop for OperationGuard {
+    fn drop(&mut self) {
+        if let Ok(mut ops) = OPERATIONS.lock() {
+            ops.remove(&self.0.id);
+        }
+        self.0.finished_tx.send_replace(true);
+    }
+}
+
+// Registers an operation; an ID that is already running is refused, since the first
+// operation to finish would otherwise unregister the other one
+pub(crate) fn start(id: String, targets: &[String]) -> Result<OperationGuard, String> {
+    let mut ops = OPERATIONS.lock().map_err(|e| e.to_string())?;
+    if ops.contains_key(&id) {
+        return Err(format!("An operation with id {} is already running", id));
+    }
+    let op = Arc::new(Operation {
+        id: id.clone(),
+        targets: targets.to_vec(),
+        completed: Mutex::new(Vec::new()),
+        cancel_tx: watch::Sender::new(false),
+        finished_tx: watch::Sender::new(false),
+    });
+    ops.insert(id, op.clone());
+    Ok(OperationGuard(op))
+}
+
+// Requests cancellation and waits for the operation to reach its next safe point
+pub(crate) async fn cancel(operation_id: &str) -> Result<OperationReport, String> {
+    let op = OPERATIONS
+        .lock()
+        .map_err(|e| e.to_string())?
+        .get(operation_id)
+        .cloned()
+        .ok_or_else(|| format!("No running operation with id {}", operation_id))?;
+    op.cancel_tx.send_replace(true);
+    let mut finished_rx = op.finished_tx.subscribe();
+    let _ = finished_rx.wait_for(|finished| *finished).await;
+    Ok(op.report())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn cancel_between_targets_reports_progress() {
+        let targets: Vec<String> = ["A", "B", "C"].iter().map(|t| t.to_string()).collect();
+        let op = start("op-test-cancel".to_string(), &targets).unwrap();
+        op.complete_target("A");
+        assert!(start("op-test-cancel".to_string(), &targets).is_err());
+
+        let cancelling = tokio::spawn(cancel("op-test-cancel"));
+        while !op.is_cancelled() {
+            tokio::task::yield_now().await;
+        }
+        // The running command reaches its next safe point and finishes
+        drop(op);
+
+        let report = cancelling.await.unwrap().unwrap();
+        assert!(report.cancelled);
+        assert_eq!(report.completed, vec!["A"]);
+        assert_eq!(report.untouched, vec!["B", "C"]);
+        assert!(cancel("op-test-cancel").await.is_err());
+    }
+}
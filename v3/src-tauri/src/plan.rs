@@ -0,0 +1,290 @@
+// Dry-run plans: what a command would download, change and record, without doing any of it
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::backend::{select_backends, BackendChoice};
+use crate::material;
+use crate::{get_cached_download, get_installed_preset, Installation, MaterialDownload};
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum DownloadSource {
+    Cache,
+    Disk,
+    Network,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub(crate) struct PlannedDownload {
+    pub url: String,
+    pub path: String,
+    pub source: DownloadSource,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum FileAction {
+    Create,
+    Replace,
+    Modify,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub(crate) struct PlannedFileChange {
+    pub path: String,
+    pub action: FileAction,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub(crate) enum TrackingChange {
+    // installed_presets.json entry set to this preset
+    Set { uuid: String, name: String },
+    // installed_presets.json entry removed
+    Remove,
+    Unchanged,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub(crate) struct TargetPlan {
+    pub install_location: String,
+    // "direct", "iobit" or "elevated"; None when no game files are written
+    pub backend: Option<String>,
+    pub changes: Vec<PlannedFileChange>,
+    pub tracking: TrackingChange,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_preset: Option<String>,
+    // Problems the install would not stop for, e.g. a material version mismatch
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub(crate) struct ActionPlan {
+    pub operation: String,
+    pub downloads: Vec<PlannedDownload>,
+    pub targets: Vec<TargetPlan>,
+    // Selections that don't match any installation
+    pub skipped: Vec<String>,
+    // Installer data the command deletes
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub removed: Vec<String>,
+}
+
+impl ActionPlan {
+    pub fn new(operation: &str) -> Self {
+        ActionPlan {
+            operation: operation.to_string(),
+            downloads: Vec::new(),
+            targets: Vec::new(),
+            skipped: Vec::new(),
+            removed: Vec::new(),
+        }
+    }
+}
+
+fn file_change(path: &Path, action_if_present: FileAction) -> PlannedFileChange {
+    PlannedFileChange {
+        path: path.to_string_lossy().to_string(),
+        action: if path.exists() { action_if_present } else { FileAction::Create },
+    }
+}
+
+// Where each download would come from, checked without touching the network or disk
+pub(crate) async fn plan_downloads(downloads: &[MaterialDownload]) -> Vec<PlannedDownload> {
+    let mut planned = Vec::new();
+    for d in downloads {
+        let source = if get_cached_download(&d.url).await.is_some() {
+            DownloadSource::Cache
+        } else {
+            DownloadSource::Network
+        };
+        planned.push(PlannedDownload {
+            url: d.url.clone(),
+            path: d.path.to_string_lossy().to_string(),
+            source,
+        });
+    }
+    planned
+}
+
+pub(crate) fn plan_local_file(path: &Path) -> PlannedDownload {
+    PlannedDownload {
+        url: path.to_string_lossy().to_string(),
+        path: path.to_string_lossy().to_string(),
+        source: DownloadSource::Disk,
+    }
+}
+
+// The first backend install_files tries, plus the ones it falls back to
+fn backend_label(choices: &[BackendChoice]) -> Option<String> {
+    let (first, fallbacks) = choices.split_first()?;
+    if fallbacks.is_empty() {
+        return Some(first.name().to_string());
+    }
+    let fallbacks: Vec<&str> = fallbacks.iter().map(|c| c.name()).collect();
+    Some(format!("{} ({} fallback)", first.name(), fallbacks.join(", ")))
+}
+
+// Plans a material install (presets, packs, uploads, uninstall) on every selected installation
+pub(crate) fn plan_material_targets(
+    plan: &mut ActionPlan,
+    material_names: &[String],
+    selected_names: &[String],
+    installations: &HashMap<String, Installation>,
+    tracking: TrackingChange,
+) {
+    for install_location in selected_names {
+        let Some(ins) = installations.get(install_location) else {
+            plan.skipped.push(install_location.clone());
+            continue;
+        };
+        let mc_dest = Path::new(&ins.install_location).join("data").join("renderer").join("materials");
+        let changes = material_names.iter().map(|name| file_change(&mc_dest.join(name), FileAction::Replace)).collect();
+        plan.targets.push(TargetPlan {
+            install_location: ins.install_location.clone(),
+            backend: backend_label(&select_backends(&mc_dest)),
+            changes,
+            tracking: tracking.clone(),
+            current_preset: get_installed_preset(&ins.install_location).map(|p| p.name),
+            warnings: Vec::new(),
+        });
+    }
+}
+
+// Plans files written to paths relative to each installation's root, such as the
+// destinations listed in a pack manifest
+pub(crate) fn plan_file_targets(
+    plan: &mut ActionPlan,
+    relative_paths: &[PathBuf],
+    selected_names: &[String],
+    installations: &HashMap<String, Installation>,
+    tracking: TrackingChange,
+) {
+    for install_location in selected_names {
+        let Some(ins) = installations.get(install_location) else {
+            plan.skipped.push(install_location.clone());
+            continue;
+        };
+        let root = Path::new(&ins.install_location);
+        let target_dir = relative_paths.first().and_then(|p| root.join(p).parent().map(Path::to_path_buf)).unwrap_or_else(|| root.to_path_buf());
+        plan.targets.push(TargetPlan {
+            install_location: ins.install_location.clone(),
+            backend: backend_label(&select_backends(&target_dir)),
+            changes: relative_paths.iter().map(|p| file_change(&root.join(p), FileAction::Replace)).collect(),
+            tracking: tracking.clone(),
+            current_preset: get_installed_preset(&ins.install_location).map(|p| p.name),
+            warnings: Vec::new(),
+        });
+    }
+}
+
+// Plans a command that reads each installation and writes a file elsewhere, e.g. a backup
+pub(crate) fn plan_output_targets(
+    plan: &mut ActionPlan,
+    selected_names: &[String],
+    installations: &HashMap<String, Installation>,
+    output: impl Fn(&Installation) -> PathBuf,
+) {
+    for install_location in selected_names {
+        let Some(ins) = installations.get(install_location) else {
+            plan.skipped.push(install_location.clone());
+            continue;
+        };
+        plan.targets.push(TargetPlan {
+            install_location: ins.install_location.clone(),
+            backend: None,
+            changes: vec![file_change(&output(ins), FileAction::Replace)],
+            tracking: TrackingChange::Unchanged,
+            current_preset: get_installed_preset(&ins.install_location).map(|p| p.name),
+            warnings: Vec::new(),
+        });
+    }
+}
+
+// Warns on each target about materials in `versions` (file name to material version)
+// that would replace a material of another version
+pub(crate) fn warn_material_versions(targets: &mut [TargetPlan], versions: &HashMap<String, u64>) {
+    for target in targets {
+        let mc_dest = Path::new(&target.install_location).join("data").join("renderer").join("materials");
+        let mut names: Vec<&String> = versions.keys().collect();
+        names.sort();
+        target.warnings.extend(names.into_iter().filter_map(|name| material::version_warning(name, versions[name], &mc_dest.join(name))));
+    }
+}
+
+// Plans a single file written into each installation's root (e.g. the DLSS DLL)
+pub(crate) fn plan_root_file_targets(
+    plan: &mut ActionPlan,
+    file_name: &str,
+    selected_names: &[String],
+    installations: &HashMap<String, Installation>,
+) {
+    plan_file_targets(plan, &[PathBuf::from(file_name)], selected_names, installations, TrackingChange::Unchanged);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn labels_fallback_backends() {
+        assert_eq!(backend_label(&[]), None);
+        assert_eq!(backend_label(&[BackendChoice::Direct]).as_deref(), Some("direct"));
+        let protected = [BackendChoice::IObit(PathBuf::from("IObitUnlocker.exe")), BackendChoice::Elevated];
+        assert_eq!(backend_label(&protected).as_deref(), Some("iobit (elevated fallback)"));
+    }
+
+    #[test]
+    fn plans_each_selected_installation() {
+        let root = std::env::temp_dir().join(format!("brtx-plan-test-{}", std::process::id()));
+        let materials = root.join("data").join("renderer").join("materials");
+        fs::create_dir_all(&materials).unwrap();
+        fs::write(materials.join("RTXStub.material.bin"), "old stub").unwrap();
+        let install_location = root.to_string_lossy().to_string();
+        let installations = HashMap::from([(
+            install_location.clone(),
+            Installation { friendly_name: "Minecraft".into(), install_location: install_location.clone(), preview: false, installed_preset: None },
+        )]);
+        let names = vec!["RTXStub.material.bin".to_string(), "RTXPostFX.Bloom.material.bin".to_string()];
+        let selected = vec![install_location.clone(), "C:\\Unknown".to_string()];
+
+        let mut plan = ActionPlan::new("install_preset");
+        plan_material_targets(&mut plan, &names, &selected, &installations, TrackingChange::Remove);
+
+        assert_eq!(plan.skipped, vec!["C:\\Unknown"]);
+        let target = &plan.targets[0];
+        assert_eq!(target.backend.as_deref(), Some("direct"));
+        let actions: Vec<FileAction> = target.changes.iter().map(|c| c.action).collect();
+        assert_eq!(actions, vec![FileAction::Replace, FileAction::Create]);
+        assert!(matches!(target.tracking, TrackingChange::Remove));
+    }
+
+    #[test]
+    fn warns_about_material_version_mismatches() {
+        let root = std::env::temp_dir().join(format!("brtx-plan-versions-test-{}", std::process::id()));
+        let materials = root.join("data").join("renderer").join("materials");
+        fs::create_dir_all(&materials).unwrap();
+        fs::write(materials.join("RTXStub.material.bin"), material::tests::material(21, "RTXStub", None)).unwrap();
+        fs::write(materials.join("RTXPostFX.Bloom.material.bin"), material::tests::material(22, "RTXPostFX.Bloom", None)).unwrap();
+        let mut plan = ActionPlan::new("install_materials");
+        plan.targets.push(TargetPlan {
+            install_location: root.to_string_lossy().to_string(),
+            backend: None,
+            changes: Vec::new(),
+            tracking: TrackingChange::Unchanged,
+            current_preset: None,
+            warnings: Vec::new(),
+        });
+        let versions = HashMap::from([
+            ("RTXStub.material.bin".to_string(), 22),
+            ("RTXPostFX.Bloom.material.bin".to_string(), 22),
+            ("RTXPostFX.Tonemapping.material.bin".to_string(), 22),
+        ]);
+
+        warn_material_versions(&mut plan.targets, &versions);
+        assert_eq!(plan.targets[0].warnings, vec!["RTXStub.material.bin is material version 22, the installation has version 21"]);
+    }
+}
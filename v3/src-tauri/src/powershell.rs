@@ -0,0 +1,168 @@
+// Builds PowerShell scripts without splicing untrusted text into code. Values are
+// emitted as single-quoted literals and whole scripts are passed with -EncodedCommand,
+// so paths containing quotes, spaces, `$`, backticks or non-ASCII text stay data.
+use std::path::Path;
+
+use base64::Engine;
+use tauri_plugin_shell::ShellExt;
+
+// PowerShell treats these as single quotes too, so each must be doubled inside a literal
+const SINGLE_QUOTES: [char; 5] = ['\'', '\u{2018}', '\u{2019}', '\u{201A}', '\u{201B}'];
+
+// A single-quoted string literal; nothing inside it is expanded
+pub(crate) fn quote(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('\'');
+    for c in value.chars() {
+        if SINGLE_QUOTES.contains(&c) {
+            out.push(c);
+        }
+        out.push(c);
+    }
+    out.push('\'');
+    out
+}
+
+pub(crate) fn quote_path(path: &Path) -> String {
+    quote(&path.to_string_lossy())
+}
+
+// Base64 of the UTF-16LE script, as -EncodedCommand expects
+pub(crate) fn encode(script: &str) -> String {
+    let bytes: Vec<u8> = script.encode_utf16().flat_map(|u| u.to_le_bytes()).collect();
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+// Arguments for powershell.exe that run `script` without it passing through the command line parser
+pub(crate) fn encoded_args(script: &str) -> Vec<String> {
+    vec![
+        "-NoProfile".to_string(),
+        "-NonInteractive".to_string(),
+        "-ExecutionPolicy".to_string(),
+        "Bypass".to_string(),
+        "-EncodedCommand".to_string(),
+        encode(script),
+    ]
+}
+
+// One command line: a name followed by parameters and quoted values
+#[derive(Debug, Clone)]
+pub(crate) struct Command {
+    parts: Vec<String>,
+}
+
+impl Command {
+    pub fn new(name: &str) -> Self {
+        Self { parts: vec![name.to_string()] }
+    }
+
+    // A parameter name or switch such as `-Force`, emitted as is
+    pub fn param(mut self, name: &str) -> Self {
+        self.parts.push(name.to_string());
+        self
+    }
+
+    pub fn literal(mut self, value: &str) -> Self {
+        self.parts.push(quote(value));
+        self
+    }
+
+    pub fn path(mut self, path: &Path) -> Self {
+        self.parts.push(quote_path(path));
+        self
+    }
+
+    // A comma-separated array of literals
+    pub fn literals<I, S>(mut self, values: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let quoted: Vec<String> = values.into_iter().map(|v| quote(v.as_ref())).collect();
+        self.parts.push(quoted.join(","));
+        self
+    }
+
+    pub fn render(&self) -> String {
+        self.parts.join(" ")
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Script {
+    lines: Vec<String>,
+}
+
+impl Script {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // A line written by the caller; it must not contain unquoted data
+    pub fn raw(mut self, line: &str) -> Self {
+        self.lines.push(line.to_string());
+        self
+    }
+
+    pub fn render(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    // Runs a Start-Process command to completion, keeping the process in `$p`
+    pub fn start_process(self, process: Command) -> Self {
+        self.raw(&format!("$p = {} -Wait -PassThru", process.render()))
+    }
+}
+
+pub(crate) async fn run(app_handle: &tauri::AppHandle, script: &Script) -> Result<tauri_plugin_shell::process::Output, String> {
+    app_handle
+        .shell()
+        .command("powershell.exe")
+        .args(encoded_args(&script.render()))
+        .output()
+        .await
+        .map_err(|e| format!("Failed to spawn PowerShell: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(encoded: &str) -> String {
+        let bytes = base64::engine::general_purpose::STANDARD.decode(encoded).unwrap();
+        let units: Vec<u16> = bytes.chunks(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+        String::from_utf16(&units).unwrap()
+    }
+
+    #[test]
+    fn quotes_are_doubled() {
+        assert_eq!(quote("it's"), "'it''s'");
+        assert_eq!(quote("a\u{2019}b\u{2018}c"), "'a\u{2019}\u{2019}b\u{2018}\u{2018}c'");
+        assert_eq!(quote("\u{201A}\u{201B}"), "'\u{201A}\u{201A}\u{201B}\u{201B}'");
+    }
+
+    #[test]
+    fn expansion_characters_are_left_alone() {
+        // Single-quoted literals never expand `$` or backticks, and backslashes are not escapes
+        assert_eq!(quote(r"C:\Users\$env:USER\`n dir"), r"'C:\Users\$env:USER\`n dir'");
+        assert_eq!(quote_path(Path::new(r"C:\Program Files\WindowsApps")), r"'C:\Program Files\WindowsApps'");
+    }
+
+    #[test]
+    fn non_ascii_survives_encoding() {
+        let script = "Remove-Item 'C:\\Spiele\\Minecraft – Vorschau\\日本語.bin'";
+        assert_eq!(decode(&encode(script)), script);
+        assert_eq!(encode("dir"), "ZABpAHIA");
+    }
+
+    #[test]
+    fn commands_render_quoted_values() {
+        let command = Command::new("Copy-Item")
+            .param("-LiteralPath")
+            .path(Path::new(r"C:\a'b\$x.bin"))
+            .param("-Destination")
+            .literals(["one", "two's"])
+            .param("-Force");
+        assert_eq!(command.render(), r"Copy-Item -LiteralPath 'C:\a''b\$x.bin' -Destination 'one','two''s' -Force");
+    }
+}
@@ -0,0 +1,141 @@
+// Per-installation results of multi-target commands. Every selected installation is
+// processed and gets a result, so one broken target does not stop the rest.
+use serde::Serialize;
+
+use crate::plan::ActionPlan;
+use crate::signing::SignatureStatus;
+use crate::transaction::ROLLED_BACK;
+use crate::verify::FileVerification;
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub(crate) enum TargetStatus {
+    // The command's change was applied to this installation
+    Installed,
+    // The selection did not match any known installation
+    SkippedUnknown,
+    Failed { reason: String },
+    // Failed after writing, and the previous files were restored
+    RolledBack { reason: String },
+    // The operation was cancelled before this installation was touched
+    Cancelled,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub(crate) struct TargetResult {
+    pub install_location: String,
+    #[serde(flatten)]
+    pub status: TargetStatus,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<FileVerification>,
+    // A file the command produced for this target, e.g. a backup
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    // Problems that did not stop the command, e.g. a material version mismatch
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+// The files installed into one installation and what to warn about them
+#[derive(Debug, Default)]
+pub(crate) struct InstalledFiles {
+    pub files: Vec<FileVerification>,
+    pub warnings: Vec<String>,
+}
+
+impl From<Vec<FileVerification>> for InstalledFiles {
+    fn from(files: Vec<FileVerification>) -> Self {
+        Self { files, warnings: Vec::new() }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub(crate) struct CommandReport {
+    // Set instead of targets when the command ran as a dry run
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plan: Option<ActionPlan>,
+    pub targets: Vec<TargetResult>,
+    // Set by commands that install an .rtpack
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<SignatureStatus>,
+}
+
+impl CommandReport {
+    pub fn planned(plan: ActionPlan) -> Self {
+        Self { plan: Some(plan), ..Self::default() }
+    }
+
+    // True when every target was installed
+    pub fn succeeded(&self) -> bool {
+        self.targets.iter().all(|t| matches!(t.status, TargetStatus::Installed))
+    }
+
+    fn push(&mut self, install_location: String, status: TargetStatus, files: Vec<FileVerification>, output: Option<String>) {
+        self.targets.push(TargetResult { install_location, status, files, output, warnings: Vec::new() });
+    }
+
+    pub fn skipped_unknown(&mut self, install_location: String) {
+        println!("⚠ Skipping unknown selection (no matching installation): {}", install_location);
+        self.push(install_location, TargetStatus::SkippedUnknown, Vec::new(), None);
+    }
+
+    pub fn cancelled(&mut self, install_location: String) {
+        self.push(install_location, TargetStatus::Cancelled, Vec::new(), None);
+    }
+
+    pub fn failed(&mut self, install_location: String, reason: String) {
+        println!("⚠ {} failed: {}", install_location, reason);
+        let status = match reason.strip_suffix(ROLLED_BACK) {
+            Some(reason) => TargetStatus::RolledBack { reason: reason.trim_end().to_string() },
+            None => TargetStatus::Failed { reason },
+        };
+        self.push(install_location, status, Vec::new(), None);
+    }
+
+    // Records the outcome of installing files into one installation
+    pub fn record(&mut self, install_location: String, result: Result<impl Into<InstalledFiles>, String>) {
+        match result.map(Into::into) {
+            Ok(InstalledFiles { files, warnings }) => {
+                self.push(install_location, TargetStatus::Installed, files, None);
+                if let Some(target) = self.targets.last_mut() {
+                    target.warnings = warnings;
+                }
+            }
+            Err(e) => self.failed(install_location, e),
+        }
+    }
+
+    // Records a target whose outcome is a produced file rather than installed files
+    pub fn record_output(&mut self, install_location: String, result: Result<String, String>) {
+        match result {
+            Ok(output) => self.push(install_location, TargetStatus::Installed, Vec::new(), Some(output)),
+            Err(e) => self.failed(install_location, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failures_are_classified_per_target() {
+        let mut report = CommandReport::default();
+        report.record("A".to_string(), Ok(InstalledFiles { files: Vec::new(), warnings: vec!["RTXStub.material.bin is material version 23".to_string()] }));
+        report.record("B".to_string(), Err::<InstalledFiles, _>(format!("Verification failed for: RTXStub.material.bin {}", ROLLED_BACK)));
+        report.record("C".to_string(), Err::<InstalledFiles, _>("Source file not found: x".to_string()));
+        report.skipped_unknown("D".to_string());
+        report.cancelled("E".to_string());
+
+        let json = serde_json::to_value(&report).unwrap();
+        let statuses: Vec<&str> = json["targets"].as_array().unwrap().iter().map(|t| t["status"].as_str().unwrap()).collect();
+        assert_eq!(statuses, vec!["installed", "rolled_back", "failed", "skipped_unknown", "cancelled"]);
+        assert_eq!(json["targets"][1]["reason"], "Verification failed for: RTXStub.material.bin");
+        assert_eq!(json["targets"][0]["warnings"][0], "RTXStub.material.bin is material version 23");
+        assert!(json["targets"][2].get("warnings").is_none());
+        assert!(json.get("plan").is_none());
+        assert!(!report.succeeded());
+        report.targets.truncate(1);
+        assert!(report.succeeded());
+    }
+}
@@ -0,0 +1,648 @@
+// .rtpack archives: a zip of material files, optionally described by a manifest.json
+// at the archive root. Packs without a manifest are installed by picking up every
+// *material*.bin they contain.
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{BufReader, Read, Seek, Write};
+use std::path::{Component, Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+
+use crate::archive::{self, ExtractProgress};
+use crate::material::{self, MaterialInfo};
+use crate::settings::{SignaturePolicy, TrustedPublisher};
+use crate::sha256_hex;
+use crate::signing::{self, SignatureStatus, SIGNATURE_FILE};
+
+pub(crate) const MANIFEST_FILE: &str = "manifest.json";
+// Newest manifest format this build understands
+pub(crate) const FORMAT_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct ManifestFile {
+    // Location inside the archive, with forward slashes
+    pub path: String,
+    pub sha256: String,
+    // Where the file goes, relative to the installation root: a material in
+    // data/renderer/materials, or nvngx_dlss.dll
+    pub destination: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct RtpackManifest {
+    pub format_version: u32,
+    pub name: String,
+    pub uuid: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    // Minecraft versions the materials were built for, e.g. "1.21.50"
+    #[serde(default)]
+    pub game_versions: Vec<String>,
+    // UUID of the preset the files were exported from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_preset: Option<String>,
+    pub files: Vec<ManifestFile>,
+}
+
+// A path from a manifest as a relative path that cannot leave the directory it is joined to
+fn relative_path(path: &str) -> Result<PathBuf, String> {
+    let p = Path::new(path);
+    if path.is_empty() || !p.components().all(|c| matches!(c, Component::Normal(_))) {
+        return Err(format!("Invalid path in manifest: {}", path));
+    }
+    Ok(p.to_path_buf())
+}
+
+// The only places a manifest may install to: the RTX materials, and the DLSS DLL
+// in the installation root
+fn allowed_destination(path: &Path) -> bool {
+    let parts: Vec<String> = path.components().map(|c| c.as_os_str().to_string_lossy().to_ascii_lowercase()).collect();
+    match parts.as_slice() {
+        [dll] => dll == "nvngx_dlss.dll",
+        [data, renderer, materials, file] => {
+            data == "data" && renderer == "renderer" && materials == "materials" && file.ends_with(".material.bin")
+        }
+        _ => false,
+    }
+}
+
+impl RtpackManifest {
+    pub fn new(name: &str, uuid: &str) -> Self {
+        Self {
+            format_version: FORMAT_VERSION,
+            name: name.to_string(),
+            uuid: uuid.to_string(),
+            author: None,
+            version: None,
+            description: None,
+            game_versions: Vec::new(),
+            source_preset: None,
+            files: Vec::new(),
+        }
+    }
+
+    pub fn parse(json: &str) -> Result<Self, String> {
+        let manifest: RtpackManifest = serde_json::from_str(json).map_err(|e| format!("Invalid {}: {e}", MANIFEST_FILE))?;
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.format_version == 0 || self.format_version > FORMAT_VERSION {
+            return Err(format!(
+                "Unsupported pack format version {} (this installer supports up to {})",
+                self.format_version, FORMAT_VERSION
+            ));
+        }
+        if self.name.trim().is_empty() || self.uuid.trim().is_empty() {
+            return Err("Pack manifest needs a name and a UUID".to_string());
+        }
+        if self.files.is_empty() {
+            return Err("Pack manifest lists no files".to_string());
+        }
+        for file in &self.files {
+            relative_path(&file.path)?;
+            if !allowed_destination(&relative_path(&file.destination)?) {
+                return Err(format!("Pack may not install to {}", file.destination));
+            }
+        }
+        Ok(())
+    }
+
+    // Destinations relative to the installation root
+    pub fn destinations(&self) -> Vec<PathBuf> {
+        self.files.iter().filter_map(|f| relative_path(&f.destination).ok()).collect()
+    }
+
+    // (extracted file, destination) pairs for one installation. Every extracted file
+    // must match the hash the manifest gives for it.
+    pub fn install_pairs(&self, extracted: &Path, install_location: &Path) -> Result<Vec<(PathBuf, PathBuf)>, String> {
+        self.files
+            .iter()
+            .map(|file| {
+                let source = extracted.join(relative_path(&file.path)?);
+                let data = fs::read(&source).map_err(|e| format!("Pack file {} is missing: {e}", file.path))?;
+                if !sha256_hex(&data).eq_ignore_ascii_case(&file.sha256) {
+                    return Err(format!("Pack file {} does not match the hash in its manifest", file.path));
+                }
+                Ok((source, install_location.join(relative_path(&file.destination)?)))
+            })
+            .collect()
+    }
+}
+
+// Material files are picked up by name in packs without a manifest
+pub(crate) fn is_material(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == "bin") && path.file_stem().is_some_and(|s| s.to_string_lossy().contains("material"))
+}
+
+type PackArchive = zip::ZipArchive<BufReader<File>>;
+
+fn open(pack: &Path) -> Result<PackArchive, String> {
+    let f = File::open(pack).map_err(|e| format!("Open pack failed: {e}"))?;
+    zip::ZipArchive::new(BufReader::new(f)).map_err(|e| format!("Invalid .rtpack: {e}"))
+}
+
+fn read_entry<R: Read + Seek>(zip: &mut zip::ZipArchive<R>, name: &str) -> Result<Option<Vec<u8>>, String> {
+    let mut entry = match zip.by_name(name) {
+        Ok(entry) => entry,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(format!("Invalid .rtpack: {e}")),
+    };
+    let mut data = Vec::new();
+    entry.read_to_end(&mut data).map_err(|e| format!("Read {} failed: {e}", name))?;
+    Ok(Some(data))
+}
+
+// Minecraft resource packs (.mcpack) have a manifest.json of their own with a "header"
+// object; that is not a pack manifest, so it reads as none
+pub(crate) fn parse_manifest(data: &[u8]) -> Result<Option<RtpackManifest>, String> {
+    let json = std::str::from_utf8(data).map_err(|e| format!("Invalid {}: {e}", MANIFEST_FILE))?;
+    if serde_json::from_str::<serde_json::Value>(json).is_ok_and(|v| v.get("header").is_some()) {
+        return Ok(None);
+    }
+    RtpackManifest::parse(json).map(Some)
+}
+
+fn manifest_in<R: Read + Seek>(zip: &mut zip::ZipArchive<R>) -> Result<Option<RtpackManifest>, String> {
+    let Some(data) = read_entry(zip, MANIFEST_FILE)? else { return Ok(None) };
+    parse_manifest(&data)
+}
+
+fn signature_in<R: Read + Seek>(zip: &mut zip::ZipArchive<R>, trusted: &[TrustedPublisher]) -> Result<SignatureStatus, String> {
+    let manifest = read_entry(zip, MANIFEST_FILE)?;
+    let signature = read_entry(zip, SIGNATURE_FILE)?;
+    signing::verify(manifest.as_deref(), signature.as_deref(), trusted)
+}
+
+// Reads the manifest straight from the archive; Ok(None) when the pack has none
+pub(crate) fn read_manifest(pack: &Path) -> Result<Option<RtpackManifest>, String> {
+    manifest_in(&mut open(pack)?)
+}
+
+pub(crate) fn read_signature(pack: &Path, trusted: &[TrustedPublisher]) -> Result<SignatureStatus, String> {
+    signature_in(&mut open(pack)?, trusted)
+}
+
+// Paths of the material entries in the archive, without extracting them
+pub(crate) fn material_entries(pack: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut zip = open(pack)?;
+    let mut paths = Vec::new();
+    for i in 0..zip.len() {
+        let file = zip.by_index_raw(i).map_err(|e| format!("Invalid .rtpack: {e}"))?;
+        if file.is_dir() {
+            continue;
+        }
+        let path = archive::entry_path(&file)?;
+        if is_material(&path) {
+            paths.push(path);
+        }
+    }
+    Ok(paths)
+}
+
+// Opens the pack after check_archive passes, with the index of each entry path
+fn open_checked(pack: &Path) -> Result<(PackArchive, Vec<(PathBuf, usize)>), String> {
+    let mut zip = open(pack)?;
+    archive::check_archive(&mut zip)?;
+    let mut indices = Vec::new();
+    for i in 0..zip.len() {
+        let file = zip.by_index_raw(i).map_err(|e| format!("Invalid .rtpack: {e}"))?;
+        indices.push((archive::entry_path(&file)?, i));
+    }
+    Ok((zip, indices))
+}
+
+fn index_of(indices: &[(PathBuf, usize)], path: &Path) -> Result<usize, String> {
+    indices.iter().find(|(p, _)| p == path).map(|(_, i)| *i).ok_or_else(|| format!("Pack does not contain {}", path.display()))
+}
+
+// Reads the entries at `paths` into memory, in the same order, after check_archive passes
+pub(crate) fn read_files(pack: &Path, paths: &[PathBuf]) -> Result<Vec<Vec<u8>>, String> {
+    let (mut zip, indices) = open_checked(pack)?;
+    paths
+        .iter()
+        .map(|path| {
+            let mut entry = zip.by_index(index_of(&indices, path)?).map_err(|e| format!("Invalid .rtpack: {e}"))?;
+            let mut data = Vec::new();
+            entry.read_to_end(&mut data).map_err(|e| format!("Read {} failed: {e}", path.display()))?;
+            Ok(data)
+        })
+        .collect()
+}
+
+// The material header of each entry at `paths`, in the same order, reading only the
+// start of each entry
+pub(crate) fn read_material_headers(pack: &Path, paths: &[PathBuf]) -> Result<Vec<Result<MaterialInfo, String>>, String> {
+    let (mut zip, indices) = open_checked(pack)?;
+    paths
+        .iter()
+        .map(|path| {
+            let entry = zip.by_index(index_of(&indices, path)?).map_err(|e| format!("Invalid .rtpack: {e}"))?;
+            Ok(material::parse_header(entry))
+        })
+        .collect()
+}
+
+pub(crate) struct ExtractedPack {
+    pub manifest: Option<RtpackManifest>,
+    pub signature: SignatureStatus,
+    pub files: Vec<PathBuf>,
+}
+
+// The manifest and signature of an archive, refused when `policy` does not allow the signature
+fn checked_header<R: Read + Seek>(
+    zip: &mut zip::ZipArchive<R>,
+    trusted: &[TrustedPublisher],
+    policy: SignaturePolicy,
+) -> Result<(Option<RtpackManifest>, SignatureStatus), String> {
+    let manifest = manifest_in(zip)?;
+    let signature = signature_in(zip, trusted)?;
+    signing::enforce(&signature, policy)?;
+    Ok((manifest, signature))
+}
+
+// Whether an archive entry is one of the files the pack installs
+fn installs_entry(manifest: &Option<RtpackManifest>) -> impl Fn(&Path) -> bool {
+    let listed: Option<Vec<PathBuf>> = manifest
+        .as_ref()
+        .map(|m| m.files.iter().filter_map(|f| relative_path(&f.path).ok()).collect());
+    move |path| match &listed {
+        Some(listed) => listed.iter().any(|l| l == path),
+        None => is_material(path),
+    }
+}
+
+// Extracts the files the pack installs into `out_dir`: those its manifest lists, or
+// every material file when it has none. Other entries such as previews stay in the
+// archive. Packs whose signature `policy` refuses are rejected before anything is
+// written, and anything already in `out_dir` is removed first so files from an
+// earlier extraction cannot be installed.
+pub(crate) fn extract(
+    pack: &Path,
+    out_dir: &Path,
+    trusted: &[TrustedPublisher],
+    policy: SignaturePolicy,
+    progress: impl FnMut(ExtractProgress),
+) -> Result<ExtractedPack, String> {
+    let mut zip = open(pack)?;
+    let (manifest, signature) = checked_header(&mut zip, trusted, policy)?;
+    if out_dir.exists() {
+        fs::remove_dir_all(out_dir).map_err(|e| format!("Failed to clear {}: {}", out_dir.display(), e))?;
+    }
+    let files = archive::extract_matching(&mut zip, out_dir, installs_entry(&manifest), progress)?;
+    Ok(ExtractedPack { manifest, signature, files })
+}
+
+// What extract() returned for a pack that an install still running has extracted
+// into `out_dir`, read from the archive without touching the directory
+pub(crate) fn already_extracted(pack: &Path, out_dir: &Path, trusted: &[TrustedPublisher], policy: SignaturePolicy) -> Result<ExtractedPack, String> {
+    let mut zip = open(pack)?;
+    let (manifest, signature) = checked_header(&mut zip, trusted, policy)?;
+    let wanted = installs_entry(&manifest);
+    let mut files = Vec::new();
+    for i in 0..zip.len() {
+        let file = zip.by_index_raw(i).map_err(|e| format!("Invalid .rtpack: {e}"))?;
+        let path = archive::entry_path(&file)?;
+        if !file.is_dir() && wanted(&path) {
+            files.push(out_dir.join(path));
+        }
+    }
+    Ok(ExtractedPack { manifest, signature, files })
+}
+
+// SHA-256 of the pack file, which names its extraction directory
+pub(crate) fn pack_hash(pack: &Path) -> Result<String, String> {
+    let mut file = File::open(pack).map_err(|e| format!("Failed to open {}: {}", pack.display(), e))?;
+    hash_entry(&mut file)
+}
+
+// Removes the extraction directories under `root` whose pack hash is not in
+// `referenced`, and returns how many were removed
+pub(crate) fn remove_unreferenced(root: &Path, referenced: &[&str]) -> usize {
+    let Ok(entries) = fs::read_dir(root) else { return 0 };
+    entries
+        .filter_map(Result::ok)
+        .filter(|e| e.path().is_dir())
+        .filter(|e| !referenced.iter().any(|hash| e.file_name().to_string_lossy().eq_ignore_ascii_case(hash)))
+        .filter(|e| fs::remove_dir_all(e.path()).is_ok())
+        .count()
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub(crate) struct PackEntry {
+    pub path: String,
+    pub size: u64,
+    // Not computed when the archive fails the extraction limits
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    // Metadata of material files that parse
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub material: Option<MaterialInfo>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub(crate) struct PresetMatch {
+    pub uuid: String,
+    pub name: String,
+}
+
+// What a pack contains, read from the archive without writing anything to disk
+#[derive(Serialize, Debug, Clone, Default)]
+pub(crate) struct PackInspection {
+    pub manifest: Option<RtpackManifest>,
+    // None when the signature could not be checked; the reason is in warnings
+    pub signature: Option<SignatureStatus>,
+    // The files an install would use
+    pub materials: Vec<PackEntry>,
+    // Entries an install would leave in the archive
+    pub ignored: Vec<String>,
+    // Catalog presets whose known hashes all match files in the pack
+    pub matching_presets: Vec<PresetMatch>,
+    pub warnings: Vec<String>,
+}
+
+fn hash_entry<R: Read>(entry: &mut R) -> Result<String, String> {
+    let mut hasher = Sha256::new();
+    std::io::copy(entry, &mut hasher).map_err(|e| e.to_string())?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+// Hashes everything written to it, keeping only the last bytes for material::check_end
+#[derive(Default)]
+struct HashTail {
+    hasher: Sha256,
+    tail: Vec<u8>,
+}
+
+impl Write for HashTail {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.hasher.update(buf);
+        self.tail.extend_from_slice(&buf[buf.len().saturating_sub(8)..]);
+        self.tail.drain(..self.tail.len().saturating_sub(8));
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+// Streams an entry through the hasher, parsing the material header from its first bytes.
+// Only the header and the last few bytes are held in memory.
+fn scan_entry<R: Read>(entry: &mut R, material: bool) -> Result<(String, Option<Result<MaterialInfo, String>>), String> {
+    let mut head = Vec::new();
+    entry.take(material::HEADER_LIMIT).read_to_end(&mut head).map_err(|e| e.to_string())?;
+    let mut sink = HashTail::default();
+    sink.write_all(&head).map_err(|e| e.to_string())?;
+    std::io::copy(entry, &mut sink).map_err(|e| e.to_string())?;
+    let info = material.then(|| material::parse_header(&head[..]).and_then(|info| material::check_end(&info, &sink.tail).map(|_| info)));
+    Ok((format!("{:x}", sink.hasher.finalize()), info))
+}
+
+// Lists the pack's contents and everything an install would object to. Problems are
+// reported as warnings so a broken pack can still be looked at.
+pub(crate) fn inspect(pack: &Path, trusted: &[TrustedPublisher]) -> Result<PackInspection, String> {
+    let mut zip = open(pack)?;
+    let mut inspection = PackInspection::default();
+    match manifest_in(&mut zip) {
+        Ok(manifest) => inspection.manifest = manifest,
+        Err(e) => inspection.warnings.push(e),
+    }
+    match signature_in(&mut zip, trusted) {
+        Ok(status) => inspection.signature = Some(status),
+        Err(e) => inspection.warnings.push(e),
+    }
+    // Hashing reads every entry, so it is skipped for archives an install would refuse
+    let within_limits = match archive::check_archive(&mut zip) {
+        Ok(()) => true,
+        Err(e) => {
+            inspection.warnings.push(e);
+            false
+        }
+    };
+
+    let listed: Option<Vec<(PathBuf, String)>> = inspection
+        .manifest
+        .as_ref()
+        .map(|m| m.files.iter().filter_map(|f| relative_path(&f.path).ok().map(|p| (p, f.sha256.clone()))).collect());
+    let mut found = Vec::new();
+    for i in 0..zip.len() {
+        let mut file = zip.by_index(i).map_err(|e| format!("Invalid .rtpack: {e}"))?;
+        let name = file.name().to_string();
+        if file.is_dir() || name == MANIFEST_FILE || name == SIGNATURE_FILE {
+            continue;
+        }
+        let path = match archive::entry_path(&file) {
+            Ok(path) => path,
+            Err(e) => {
+                inspection.warnings.push(e);
+                continue;
+            }
+        };
+        if archive::is_symlink(&file) {
+            inspection.warnings.push(format!("Archive entry is a symbolic link: {}", name));
+            continue;
+        }
+        let expected = match &listed {
+            Some(listed) => match listed.iter().find(|(p, _)| *p == path) {
+                Some((_, hash)) => Some(hash.clone()),
+                None => {
+                    inspection.ignored.push(name);
+                    continue;
+                }
+            },
+            None if is_material(&path) => None,
+            None => {
+                inspection.ignored.push(name);
+                continue;
+            }
+        };
+        let (sha256, material) = match within_limits {
+            true => {
+                let (sha256, material) = scan_entry(&mut file, is_material(&path)).map_err(|e| format!("Read {} failed: {e}", name))?;
+                (Some(sha256), material)
+            }
+            false => (None, None),
+        };
+        let material = match material {
+            Some(Ok(info)) => Some(info),
+            Some(Err(e)) => {
+                inspection.warnings.push(format!("{} is not a valid material: {}", name, e));
+                None
+            }
+            None => None,
+        };
+        if let (Some(expected), Some(actual)) = (&expected, &sha256) {
+            if !expected.eq_ignore_ascii_case(actual) {
+                inspection.warnings.push(format!("Pack file {} does not match the hash in its manifest", name));
+            }
+        }
+        if let Some(file_name) = path.file_name().map(|n| n.to_string_lossy().to_string()) {
+            if is_material(&path) && !crate::RTX_MATERIAL_FILES.contains(&file_name.as_str()) {
+                inspection.warnings.push(format!("Unknown material name: {}", file_name));
+            }
+        }
+        found.push(path);
+        inspection.materials.push(PackEntry { path: name, size: file.size(), sha256, material });
+    }
+    if let Some(listed) = &listed {
+        for (path, _) in listed.iter().filter(|(p, _)| !found.contains(p)) {
+            inspection.warnings.push(format!("Manifest lists a file the pack does not contain: {}", path.display()));
+        }
+    }
+    if inspection.materials.is_empty() {
+        inspection.warnings.push("No materials found in pack".to_string());
+    }
+    Ok(inspection)
+}
+
+// Writes `manifest` and the files into a new pack at `dest`. Each (source, destination)
+// pair is stored under its destination path and listed in the manifest with its hash.
+pub(crate) fn write_pack(dest: &Path, mut manifest: RtpackManifest, files: &[(PathBuf, String)]) -> Result<RtpackManifest, String> {
+    manifest.files = files
+        .iter()
+        .map(|(source, destination)| {
+            relative_path(destination)?;
+            let data = fs::read(source).map_err(|e| format!("Failed to read {}: {e}", source.display()))?;
+            Ok(ManifestFile { path: destination.clone(), sha256: sha256_hex(&data), destination: destination.clone() })
+        })
+        .collect::<Result<_, String>>()?;
+    manifest.validate()?;
+
+    let file = File::create(dest).map_err(|e| format!("Failed to create {}: {e}", dest.display()))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    let json = serde_json::to_string_pretty(&manifest).map_err(|e| e.to_string())?;
+    zip.start_file(MANIFEST_FILE, options).map_err(|e| e.to_string())?;
+    zip.write_all(json.as_bytes()).map_err(|e| e.to_string())?;
+    for (source, destination) in files {
+        zip.start_file(destination.as_str(), options).map_err(|e| e.to_string())?;
+        let mut f = File::open(source).map_err(|e| e.to_string())?;
+        std::io::copy(&mut f, &mut zip).map_err(|e| e.to_string())?;
+    }
+    zip.finish().map_err(|e| e.to_string())?;
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(files: &str) -> String {
+        format!(r#"{{"format_version":1,"name":"Vanilla RTX","uuid":"b6f8","game_versions":["1.21.50"],"files":{files}}}"#)
+    }
+
+    #[test]
+    fn parses_and_validates_manifests() {
+        let ok = RtpackManifest::parse(&manifest(
+            r#"[{"path":"materials/RTXStub.material.bin","sha256":"00","destination":"data/renderer/materials/RTXStub.material.bin"}]"#,
+        ))
+        .unwrap();
+        assert_eq!(ok.destinations(), vec![PathBuf::from("data/renderer/materials/RTXStub.material.bin")]);
+        assert_eq!(ok.game_versions, vec!["1.21.50"]);
+
+        let escaping = manifest(r#"[{"path":"RTXStub.material.bin","sha256":"00","destination":"../../Windows/evil.dll"}]"#);
+        assert!(RtpackManifest::parse(&escaping).unwrap_err().contains("Invalid path"));
+        for hostile in ["AppxManifest.xml", "Minecraft.Windows.exe", "data/renderer/materials/../evil.dll", "data/resource_packs/evil.material.bin", "data/renderer/materials/sub/RTXStub.material.bin"] {
+            let json = manifest(&format!(r#"[{{"path":"RTXStub.material.bin","sha256":"00","destination":"{hostile}"}}]"#));
+            assert!(RtpackManifest::parse(&json).is_err(), "{hostile}");
+        }
+        let dlss = manifest(r#"[{"path":"dlss/nvngx_dlss.dll","sha256":"00","destination":"nvngx_dlss.dll"}]"#);
+        assert!(RtpackManifest::parse(&dlss).is_ok());
+        assert!(RtpackManifest::parse(&manifest("[]")).unwrap_err().contains("no files"));
+        let future = manifest("[]").replace(r#""format_version":1"#, r#""format_version":99"#);
+        assert!(RtpackManifest::parse(&future).unwrap_err().contains("Unsupported pack format"));
+    }
+
+    #[test]
+    fn install_pairs_check_hashes() {
+        let dir = std::env::temp_dir().join(format!("brtx-rtpack-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("RTXStub.material.bin"), "stub").unwrap();
+        let files = |hash: &str| {
+            format!(r#"[{{"path":"RTXStub.material.bin","sha256":"{hash}","destination":"data/renderer/materials/RTXStub.material.bin"}}]"#)
+        };
+
+        let good = RtpackManifest::parse(&manifest(&files(&sha256_hex(b"stub")))).unwrap();
+        let pairs = good.install_pairs(&dir, Path::new("game")).unwrap();
+        assert_eq!(pairs[0].1, Path::new("game").join("data/renderer/materials/RTXStub.material.bin"));
+
+        let bad = RtpackManifest::parse(&manifest(&files("00"))).unwrap();
+        assert!(bad.install_pairs(&dir, Path::new("game")).unwrap_err().contains("does not match"));
+    }
+
+    #[test]
+    fn written_packs_round_trip() {
+        let dir = std::env::temp_dir().join(format!("brtx-rtpack-write-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let stub = dir.join("RTXStub.material.bin");
+        fs::write(&stub, "stub").unwrap();
+        let pack = dir.join("export.rtpack");
+
+        let mut manifest = RtpackManifest::new("Vanilla RTX", "b6f8");
+        manifest.source_preset = Some("b6f8".to_string());
+        write_pack(&pack, manifest, &[(stub, "data/renderer/materials/RTXStub.material.bin".to_string())]).unwrap();
+
+        let extracted = extract(&pack, &dir.join("out"), &[], SignaturePolicy::AllowUnsigned, |_| {}).unwrap();
+        assert_eq!(extracted.signature, SignatureStatus::Unsigned);
+        let manifest = extracted.manifest.unwrap();
+        assert_eq!(manifest.source_preset.as_deref(), Some("b6f8"));
+        let pairs = manifest.install_pairs(&dir.join("out"), Path::new("game")).unwrap();
+        assert_eq!(pairs[0].1, Path::new("game").join("data/renderer/materials/RTXStub.material.bin"));
+
+        // Extracting again starts from an empty directory
+        fs::write(dir.join("out").join("stale.material.bin"), "old").unwrap();
+        // A pack the signature policy refuses is rejected before anything is touched
+        let refused = extract(&pack, &dir.join("out"), &[], SignaturePolicy::RequireSigned, |_| {});
+        assert!(refused.is_err());
+        assert!(dir.join("out").join("stale.material.bin").exists());
+        let fresh = extract(&pack, &dir.join("out"), &[], SignaturePolicy::AllowUnsigned, |_| {}).unwrap();
+        assert!(!dir.join("out").join("stale.material.bin").exists());
+        let reused = already_extracted(&pack, &dir.join("out"), &[], SignaturePolicy::AllowUnsigned).unwrap();
+        assert_eq!(reused.files, fresh.files);
+
+        let hash = pack_hash(&pack).unwrap();
+        let root = dir.join("extracted");
+        for name in [hash.as_str(), "stale"] {
+            fs::create_dir_all(root.join(name)).unwrap();
+        }
+        assert_eq!(remove_unreferenced(&root, &[&hash]), 1);
+        assert!(root.join(&hash).exists());
+    }
+
+    #[test]
+    fn inspects_without_extracting() {
+        let dir = std::env::temp_dir().join(format!("brtx-rtpack-inspect-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let pack = dir.join("loose.rtpack");
+        let mut writer = zip::ZipWriter::new(File::create(&pack).unwrap());
+        let stub = crate::material::tests::material(22, "RTXStub", None);
+        let cut = &stub[..stub.len() - 3];
+        for (name, data) in [("RTXStub.material.bin", &stub[..]), ("Custom.material.bin", b"<html>"), ("RTXPostFX.Bloom.material.bin", cut), ("preview.png", b"png")] {
+            writer.start_file(name, zip::write::FileOptions::default()).unwrap();
+            writer.write_all(data).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let inspection = inspect(&pack, &[]).unwrap();
+        assert!(inspection.manifest.is_none());
+        assert_eq!(inspection.signature, Some(SignatureStatus::Unsigned));
+        assert_eq!(inspection.materials.len(), 3);
+        assert_eq!(inspection.materials[0].sha256.as_deref(), Some(sha256_hex(&stub).as_str()));
+        assert_eq!(inspection.materials[0].material.as_ref().and_then(|m| m.name.as_deref()), Some("RTXStub"));
+        assert_eq!(inspection.ignored, vec!["preview.png"]);
+        assert_eq!(inspection.warnings, vec![
+            "Custom.material.bin is not a valid material: Not a material file",
+            "Unknown material name: Custom.material.bin",
+            "RTXPostFX.Bloom.material.bin is not a valid material: Material file is truncated",
+        ]);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+    }
+}
@@ -0,0 +1,142 @@
+// Installer-wide settings persisted in brtx_dir()/settings.json
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::{brtx_dir, read_json_file, write_json_file};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub(crate) struct InstallerSettings {
+    pub proxy: ProxySettings,
+    // Extra root certificates trusted on top of the system store.
+    // Each entry is either inline PEM text or a path to a PEM file.
+    pub extra_ca_certificates: Vec<String>,
+    // Opt out of reading back installed files and comparing them with their source
+    pub skip_file_verification: bool,
+    // Publishers whose signed .rtpack files are reported as trusted
+    pub trusted_publishers: Vec<TrustedPublisher>,
+    pub pack_signature_policy: SignaturePolicy,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct TrustedPublisher {
+    pub name: String,
+    // Base64 Ed25519 public key
+    pub public_key: String,
+}
+
+// Which .rtpack files may be installed, by signature
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum SignaturePolicy {
+    #[default]
+    AllowUnsigned,
+    // Any valid signature, trusted or not
+    RequireSigned,
+    // Only packs signed by a trusted publisher
+    RequireTrusted,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ProxyMode {
+    // Honor HTTP_PROXY / HTTPS_PROXY / ALL_PROXY / NO_PROXY from the environment
+    #[default]
+    System,
+    // Connect directly, ignoring any environment proxy
+    None,
+    // Use the proxies configured below
+    Manual,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub(crate) struct ProxySettings {
+    pub mode: ProxyMode,
+    // e.g. http://proxy.corp:8080
+    pub http: Option<String>,
+    pub https: Option<String>,
+    // Used for every scheme, e.g. socks5://proxy.corp:1080
+    pub all: Option<String>,
+    // Comma-separated hosts that bypass the proxy, same syntax as NO_PROXY
+    pub no_proxy: Option<String>,
+    pub username: Option<String>,
+    // Never sent to the UI; None on save keeps the stored password, "" clears it
+    pub password: Option<String>,
+    // Whether a password is stored, reported to the UI instead of the password itself
+    #[serde(skip_deserializing)]
+    pub has_password: bool,
+}
+
+impl InstallerSettings {
+    // Copy of the settings that is safe to hand to the webview
+    pub(crate) fn redacted(mut self) -> Self {
+        self.proxy.has_password = self.proxy.password.as_deref().is_some_and(|p| !p.is_empty());
+        self.proxy.password = None;
+        self
+    }
+
+    // Settings coming back from the UI, with the stored password filled back in
+    // when the UI left it untouched
+    pub(crate) fn with_stored_password(mut self, stored: &InstallerSettings) -> Self {
+        match self.proxy.password.as_deref() {
+            None => self.proxy.password = stored.proxy.password.clone(),
+            Some("") => self.proxy.password = None,
+            Some(_) => {}
+        }
+        self.proxy.has_password = false;
+        self
+    }
+}
+
+fn settings_file_path() -> PathBuf {
+    brtx_dir().join("settings.json")
+}
+
+pub(crate) fn load_settings() -> InstallerSettings {
+    read_json_file(&settings_file_path()).unwrap_or_default()
+}
+
+pub(crate) fn save_settings(settings: &InstallerSettings) -> Result<(), String> {
+    write_json_file(&settings_file_path(), settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_password(password: Option<&str>) -> InstallerSettings {
+        let mut settings = InstallerSettings::default();
+        settings.proxy.username = Some("user".into());
+        settings.proxy.password = password.map(String::from);
+        settings
+    }
+
+    #[test]
+    fn password_is_not_returned_to_the_ui() {
+        let redacted = with_password(Some("secret")).redacted();
+        assert_eq!(redacted.proxy.password, None);
+        assert!(redacted.proxy.has_password);
+        assert!(!serde_json::to_string(&redacted).unwrap().contains("secret"));
+        assert!(!with_password(None).redacted().proxy.has_password);
+    }
+
+    #[test]
+    fn untouched_password_is_kept_on_save() {
+        let stored = with_password(Some("secret"));
+        let kept = stored.clone().redacted().with_stored_password(&stored);
+        assert_eq!(kept.proxy.password.as_deref(), Some("secret"));
+        let changed = with_password(Some("new")).with_stored_password(&stored);
+        assert_eq!(changed.proxy.password.as_deref(), Some("new"));
+        let cleared = with_password(Some("")).with_stored_password(&stored);
+        assert_eq!(cleared.proxy.password, None);
+    }
+
+    #[test]
+    fn missing_fields_use_defaults() {
+        let settings: InstallerSettings = serde_json::from_str(r#"{"proxy":{"mode":"manual","has_password":true}}"#).unwrap();
+        assert_eq!(settings.proxy.mode, ProxyMode::Manual);
+        assert!(!settings.proxy.has_password);
+        assert_eq!(settings.pack_signature_policy, SignaturePolicy::AllowUnsigned);
+    }
+}
@@ -0,0 +1,123 @@
+// Publisher signatures for .rtpack files. A signed pack carries signature.json next to
+// its manifest: an Ed25519 signature over the exact manifest.json bytes. The manifest
+// lists every file's hash, so the signature covers the installed files as well.
+use base64::Engine;
+use ring::signature::{UnparsedPublicKey, ED25519};
+use serde::{Deserialize, Serialize};
+
+use crate::settings::{SignaturePolicy, TrustedPublisher};
+
+pub(crate) const SIGNATURE_FILE: &str = "signature.json";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct PackSignature {
+    // Only "ed25519" is supported
+    pub algorithm: String,
+    // Base64 of the 32-byte public key
+    pub public_key: String,
+    // Base64 of the 64-byte signature over manifest.json
+    pub signature: String,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub(crate) enum SignatureStatus {
+    SignedTrusted { publisher: String, public_key: String },
+    // Validly signed by a key that is not in the trusted publishers
+    SignedUnknown { public_key: String },
+    Unsigned,
+}
+
+fn decode(value: &str, what: &str, len: usize) -> Result<Vec<u8>, String> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(value.trim())
+        .map_err(|e| format!("Invalid {what}: {e}"))?;
+    if bytes.len() != len {
+        return Err(format!("Invalid {what}: expected {len} bytes, got {}", bytes.len()));
+    }
+    Ok(bytes)
+}
+
+// Checks that a publisher key is a base64 Ed25519 public key
+pub(crate) fn parse_public_key(public_key: &str) -> Result<Vec<u8>, String> {
+    decode(public_key, "publisher key", 32)
+}
+
+// Verifies the pack's signature file against its manifest bytes. A signature that does
+// not match is an error rather than a status: the pack was modified after signing.
+pub(crate) fn verify(manifest: Option<&[u8]>, signature: Option<&[u8]>, trusted: &[TrustedPublisher]) -> Result<SignatureStatus, String> {
+    let Some(signature) = signature else { return Ok(SignatureStatus::Unsigned) };
+    let manifest = manifest.ok_or("Pack has a signature but no manifest")?;
+    let signature: PackSignature = serde_json::from_slice(signature).map_err(|e| format!("Invalid {}: {e}", SIGNATURE_FILE))?;
+    if !signature.algorithm.eq_ignore_ascii_case("ed25519") {
+        return Err(format!("Unsupported pack signature algorithm: {}", signature.algorithm));
+    }
+    let key = parse_public_key(&signature.public_key)?;
+    let sig = decode(&signature.signature, "pack signature", 64)?;
+    UnparsedPublicKey::new(&ED25519, &key)
+        .verify(manifest, &sig)
+        .map_err(|_| "Pack signature does not match its manifest; the pack was modified after signing".to_string())?;
+
+    let public_key = base64::engine::general_purpose::STANDARD.encode(&key);
+    Ok(match trusted.iter().find(|t| parse_public_key(&t.public_key).is_ok_and(|k| k == key)) {
+        Some(publisher) => SignatureStatus::SignedTrusted { publisher: publisher.name.clone(), public_key },
+        None => SignatureStatus::SignedUnknown { public_key },
+    })
+}
+
+// Refuses packs the user's policy does not allow
+pub(crate) fn enforce(status: &SignatureStatus, policy: SignaturePolicy) -> Result<(), String> {
+    match (policy, status) {
+        (SignaturePolicy::RequireSigned | SignaturePolicy::RequireTrusted, SignatureStatus::Unsigned) => {
+            Err("Unsigned packs are not allowed by your settings".to_string())
+        }
+        (SignaturePolicy::RequireTrusted, SignatureStatus::SignedUnknown { public_key }) => {
+            Err(format!("Pack is signed by an untrusted publisher key: {}", public_key))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::rand::SystemRandom;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+
+    fn sign(manifest: &[u8]) -> (Vec<u8>, String) {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        let pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+        let b64 = base64::engine::general_purpose::STANDARD;
+        let public_key = b64.encode(pair.public_key().as_ref());
+        let signature = PackSignature {
+            algorithm: "ed25519".to_string(),
+            public_key: public_key.clone(),
+            signature: b64.encode(pair.sign(manifest).as_ref()),
+        };
+        (serde_json::to_vec(&signature).unwrap(), public_key)
+    }
+
+    #[test]
+    fn classifies_signatures() {
+        let manifest = br#"{"format_version":1}"#;
+        let (signature, public_key) = sign(manifest);
+        let trusted = vec![TrustedPublisher { name: "BetterRTX".to_string(), public_key: public_key.clone() }];
+
+        assert_eq!(verify(Some(manifest), None, &trusted).unwrap(), SignatureStatus::Unsigned);
+        assert_eq!(
+            verify(Some(manifest), Some(&signature), &trusted).unwrap(),
+            SignatureStatus::SignedTrusted { publisher: "BetterRTX".to_string(), public_key: public_key.clone() }
+        );
+        assert_eq!(verify(Some(manifest), Some(&signature), &[]).unwrap(), SignatureStatus::SignedUnknown { public_key });
+        assert!(verify(Some(br#"{"format_version":2}"#), Some(&signature), &trusted).unwrap_err().contains("modified"));
+    }
+
+    #[test]
+    fn policy_refuses_unsigned_and_untrusted_packs() {
+        let unknown = SignatureStatus::SignedUnknown { public_key: "key".to_string() };
+        assert!(enforce(&SignatureStatus::Unsigned, SignaturePolicy::AllowUnsigned).is_ok());
+        assert!(enforce(&SignatureStatus::Unsigned, SignaturePolicy::RequireSigned).is_err());
+        assert!(enforce(&unknown, SignaturePolicy::RequireSigned).is_ok());
+        assert!(enforce(&unknown, SignaturePolicy::RequireTrusted).is_err());
+    }
+}
@@ -0,0 +1,262 @@
+// Transactional installs: snapshot the files a backend will replace, apply the new
+// set, verify it and restore the snapshot if any step fails.
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::backend::{select_backends, FileBackend};
+use crate::verify::FileVerification;
+use crate::{brtx_dir, ensure_dir, unique_name};
+
+// Suffix of install errors after which the previous files were restored
+pub(crate) const ROLLED_BACK: &str = "(changes rolled back)";
+
+#[derive(Debug)]
+struct SnapshotEntry {
+    target: PathBuf,
+    // Copy of the target taken before the install, if it existed
+    saved: Option<PathBuf>,
+}
+
+// Copies of the files a transaction replaces. The copies are removed when the
+// snapshot is dropped unless it was kept for undo or a failed rollback.
+#[derive(Debug)]
+pub(crate) struct InstallSnapshot {
+    dir: PathBuf,
+    entries: Vec<SnapshotEntry>,
+    keep: bool,
+}
+
+impl InstallSnapshot {
+    fn targets(&self) -> Vec<PathBuf> {
+        self.entries.iter().map(|e| e.target.clone()).collect()
+    }
+
+    // (snapshot copy, original location) pairs for the files that existed before the install
+    fn saved_pairs(&self) -> Vec<(PathBuf, PathBuf)> {
+        self.entries
+            .iter()
+            .filter_map(|e| e.saved.clone().map(|s| (s, e.target.clone())))
+            .collect()
+    }
+
+    // Moves the snapshot to `dest` and returns each target with the path of its saved
+    // copy relative to `dest`, or None for targets that did not exist before the install
+    pub fn persist(mut self, dest: &Path) -> Result<Vec<(PathBuf, Option<PathBuf>)>, String> {
+        if let Some(parent) = dest.parent() {
+            ensure_dir(parent).map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+        }
+        fs::rename(&self.dir, dest).map_err(|e| format!("Failed to keep snapshot: {e}"))?;
+        self.keep = true;
+        Ok(self
+            .entries
+            .iter()
+            .map(|e| {
+                let saved = e.saved.as_ref().and_then(|s| s.strip_prefix(&self.dir).ok()).map(Path::to_path_buf);
+                (e.target.clone(), saved)
+            })
+            .collect())
+    }
+}
+
+impl Drop for InstallSnapshot {
+    fn drop(&mut self) {
+        if !self.keep {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+}
+
+fn snapshot_files(snapshot_root: &Path, targets: &[PathBuf]) -> Result<InstallSnapshot, String> {
+    // Jobs on different installations snapshot at the same time
+    let dir = snapshot_root.join(unique_name("snapshot"));
+    ensure_dir(&dir).map_err(|e| format!("Failed to create snapshot dir: {e}"))?;
+    let mut snapshot = InstallSnapshot { dir, entries: Vec::new(), keep: false };
+    for (i, target) in targets.iter().enumerate() {
+        let saved = if target.exists() {
+            // One subdirectory per entry keeps the original file name, which IObit needs to copy it back
+            let file_name = target.file_name().ok_or_else(|| format!("Invalid target: {}", target.display()))?;
+            let entry_dir = snapshot.dir.join(i.to_string());
+            let saved = entry_dir.join(file_name);
+            ensure_dir(&entry_dir)
+                .and_then(|_| fs::copy(target, &saved))
+                .map_err(|e| format!("Failed to snapshot {}: {e}", target.display()))?;
+            Some(saved)
+        } else {
+            None
+        };
+        snapshot.entries.push(SnapshotEntry { target: target.clone(), saved });
+    }
+    Ok(snapshot)
+}
+
+async fn restore(backend: &dyn FileBackend, snapshot: &InstallSnapshot) -> Result<(), String> {
+    // Replacing every target covers both the replaced and the newly created files
+    backend.replace(&snapshot.targets(), &snapshot.saved_pairs()).await.map(|_| ())
+}
+
+// Replaces `targets` with the (source, destination) pairs as a single transaction
+// through `backend`: every target is removed, then the pairs are copied and verified.
+// The previous files are snapshotted below `snapshot_root`. On success the snapshot
+// is returned; on failure the files are restored and the error says whether that worked.
+pub(crate) async fn replace_transaction(
+    snapshot_root: &Path,
+    backend: &dyn FileBackend,
+    targets: &[PathBuf],
+    pairs: &[(PathBuf, PathBuf)],
+) -> Result<(Vec<FileVerification>, InstallSnapshot), String> {
+    if let Some((src, _)) = pairs.iter().find(|(src, _)| !src.exists()) {
+        return Err(format!("Source file not found: {}", src.display()));
+    }
+    let mut snapshot = snapshot_files(snapshot_root, targets)?;
+
+    // Every file must be present at the destination with the source's size and hash
+    match backend.replace(targets, pairs).await {
+        Ok(verification) => Ok((verification, snapshot)),
+        Err(e) => {
+            println!("⚠ Install via {} failed, rolling back: {}", backend.name(), e);
+            match restore(backend, &snapshot).await {
+                Ok(()) => Err(format!("{} {}", e, ROLLED_BACK)),
+                Err(restore_err) => {
+                    // Keep the snapshot on disk so the files can still be recovered by hand
+                    snapshot.keep = true;
+                    Err(format!(
+                        "{} (rollback failed: {}; previous files kept in {})",
+                        e,
+                        restore_err,
+                        snapshot.dir.display()
+                    ))
+                }
+            }
+        }
+    }
+}
+
+// The result of install_files and the backend that produced it
+pub(crate) struct InstallAttempt {
+    // The backend that succeeded, or the last one tried
+    pub backend: Option<&'static str>,
+    pub result: Result<Vec<FileVerification>, String>,
+    // The files the install replaced, set when it succeeded
+    pub snapshot: Option<InstallSnapshot>,
+}
+
+// Replaces `targets` in `target_dir` with the pairs using each backend select_backends
+// allows, falling back to the next one when a transaction fails
+pub(crate) async fn replace_files(
+    app_handle: &tauri::AppHandle,
+    target_dir: &Path,
+    targets: &[PathBuf],
+    pairs: &[(PathBuf, PathBuf)],
+) -> InstallAttempt {
+    let mut attempt = InstallAttempt { backend: None, result: Err("No file backend available".to_string()), snapshot: None };
+    let snapshot_root = brtx_dir().join("transactions");
+    for choice in select_backends(target_dir) {
+        if attempt.backend.is_some() {
+            println!("Attempting {} fallback...", choice.name());
+        }
+        let backend = choice.open(app_handle);
+        attempt.backend = Some(backend.name());
+        match replace_transaction(&snapshot_root, backend.as_ref(), targets, pairs).await {
+            Ok((verification, snapshot)) => {
+                attempt.result = Ok(verification);
+                attempt.snapshot = Some(snapshot);
+                break;
+            }
+            Err(e) => {
+                println!("⚠ {} backend failed: {}", backend.name(), e);
+                attempt.result = Err(e);
+            }
+        }
+    }
+    attempt
+}
+
+pub(crate) async fn install_files(app_handle: &tauri::AppHandle, target_dir: &Path, pairs: &[(PathBuf, PathBuf)]) -> InstallAttempt {
+    let targets: Vec<PathBuf> = pairs.iter().map(|(_, dest)| dest.clone()).collect();
+    replace_files(app_handle, target_dir, &targets, pairs).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+
+    use super::*;
+    use crate::backend::fake::{FileOp, RecordingBackend};
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("brtx-test-{}", std::process::id())).join(name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    async fn install_transaction(backend: &RecordingBackend, pairs: &[(PathBuf, PathBuf)]) -> Result<(Vec<FileVerification>, InstallSnapshot), String> {
+        let targets: Vec<PathBuf> = pairs.iter().map(|(_, dest)| dest.clone()).collect();
+        let snapshots = pairs[0].1.parent().unwrap().join("snapshots");
+        replace_transaction(&snapshots, backend, &targets, pairs).await
+    }
+
+    fn write(path: &Path, contents: &str) -> PathBuf {
+        fs::write(path, contents).unwrap();
+        path.to_path_buf()
+    }
+
+    #[tokio::test]
+    async fn replaces_files_and_records_operations() {
+        let dir = scratch_dir("replace");
+        let stub = write(&dir.join("RTXStub.material.bin"), "new stub");
+        let bloom = write(&dir.join("RTXPostFX.Bloom.material.bin"), "new bloom");
+        let dest = dir.join("materials");
+        fs::create_dir_all(&dest).unwrap();
+        let old_stub = write(&dest.join("RTXStub.material.bin"), "old stub");
+        let new_bloom = dest.join("RTXPostFX.Bloom.material.bin");
+        let pairs = vec![(stub.clone(), old_stub.clone()), (bloom.clone(), new_bloom.clone())];
+
+        let backend = RecordingBackend::default();
+        let (verification, _) = install_transaction(&backend, &pairs).await.unwrap();
+
+        assert_eq!(verification.len(), 2);
+        assert_eq!(fs::read_to_string(&old_stub).unwrap(), "new stub");
+        assert_eq!(fs::read_to_string(&new_bloom).unwrap(), "new bloom");
+        assert_eq!(
+            backend.ops(),
+            vec![
+                FileOp::Delete(old_stub.clone()),
+                FileOp::Copy(stub, old_stub),
+                FileOp::Copy(bloom, new_bloom),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_copy_restores_previous_files() {
+        let dir = scratch_dir("rollback");
+        let stub = write(&dir.join("RTXStub.material.bin"), "new stub");
+        let bloom = write(&dir.join("RTXPostFX.Bloom.material.bin"), "new bloom");
+        let dest = dir.join("materials");
+        fs::create_dir_all(&dest).unwrap();
+        let old_stub = write(&dest.join("RTXStub.material.bin"), "old stub");
+        let new_bloom = dest.join("RTXPostFX.Bloom.material.bin");
+        let pairs = vec![(stub, old_stub.clone()), (bloom, new_bloom.clone())];
+
+        let backend = RecordingBackend::default();
+        backend.fail_next_copy.store(true, Ordering::SeqCst);
+        let err = install_transaction(&backend, &pairs).await.unwrap_err();
+
+        assert!(err.ends_with(ROLLED_BACK), "{err}");
+        assert_eq!(fs::read_to_string(&old_stub).unwrap(), "old stub");
+        assert!(!new_bloom.exists());
+    }
+
+    #[tokio::test]
+    async fn missing_source_touches_nothing() {
+        let dir = scratch_dir("missing");
+        let dest = write(&dir.join("RTXStub.material.bin"), "old stub");
+        let backend = RecordingBackend::default();
+        let err = install_transaction(&backend, &[(dir.join("absent.bin"), dest.clone())]).await.unwrap_err();
+
+        assert!(err.starts_with("Source file not found"), "{err}");
+        assert!(backend.ops().is_empty());
+        assert_eq!(fs::read_to_string(&dest).unwrap(), "old stub");
+    }
+}
@@ -0,0 +1,145 @@
+// Undo points: the files each install replaced, kept with the installation's preset
+// record from before the install. Only the most recent few are kept per installation.
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::journal::{location_key, JournalOperation};
+use crate::transaction::InstallSnapshot;
+use crate::{brtx_dir, read_json_file, write_json_file, InstalledPreset};
+
+// Undo points kept per installation; older ones are deleted
+const UNDO_LIMIT: usize = 5;
+const POINT_FILE: &str = "undo.json";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct UndoFile {
+    pub target: PathBuf,
+    // The previous file, relative to the undo point; None if the install created the target
+    pub saved: Option<PathBuf>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct UndoPoint {
+    pub created_at: String,
+    pub install_location: String,
+    pub operation: JournalOperation,
+    pub files: Vec<UndoFile>,
+    // The installed_presets.json record before the install; None if there was none
+    pub previous_preset: Option<InstalledPreset>,
+    #[serde(skip)]
+    dir: PathBuf,
+}
+
+impl UndoPoint {
+    pub fn targets(&self) -> Vec<PathBuf> {
+        self.files.iter().map(|f| f.target.clone()).collect()
+    }
+
+    // (saved copy, original location) pairs for the files to put back
+    pub fn saved_pairs(&self) -> Vec<(PathBuf, PathBuf)> {
+        self.files
+            .iter()
+            .filter_map(|f| f.saved.as_ref().map(|s| (self.dir.join(s), f.target.clone())))
+            .collect()
+    }
+
+    // Removes the point once it has been restored
+    pub fn discard(self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn undo_root(install_location: &str) -> PathBuf {
+    brtx_dir().join("undo").join(location_key(install_location))
+}
+
+// Point directories are named by creation time, so sorting puts the oldest first
+fn point_dirs(root: &Path) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = fs::read_dir(root)
+        .map(|rd| rd.filter_map(Result::ok).map(|e| e.path()).filter(|p| p.is_dir()).collect())
+        .unwrap_or_default();
+    dirs.sort();
+    dirs
+}
+
+fn keep_in(root: &Path, install_location: &str, operation: JournalOperation, snapshot: InstallSnapshot, previous_preset: Option<InstalledPreset>) -> Result<(), String> {
+    let dir = root.join(chrono::Utc::now().format("%Y%m%d%H%M%S%f").to_string());
+    let files = snapshot
+        .persist(&dir)?
+        .into_iter()
+        .map(|(target, saved)| UndoFile { target, saved })
+        .collect();
+    let point = UndoPoint {
+        created_at: chrono::Utc::now().to_rfc3339(),
+        install_location: install_location.to_string(),
+        operation,
+        files,
+        previous_preset,
+        dir: dir.clone(),
+    };
+    if let Err(e) = write_json_file(&dir.join(POINT_FILE), &point) {
+        let _ = fs::remove_dir_all(&dir);
+        return Err(e);
+    }
+    let dirs = point_dirs(root);
+    for old in &dirs[..dirs.len().saturating_sub(UNDO_LIMIT)] {
+        let _ = fs::remove_dir_all(old);
+    }
+    Ok(())
+}
+
+fn latest_in(root: &Path) -> Result<Option<UndoPoint>, String> {
+    let Some(dir) = point_dirs(root).pop() else { return Ok(None) };
+    // An unreadable newest point must not let undo skip back to an older state
+    let mut point: UndoPoint = read_json_file(&dir.join(POINT_FILE)).ok_or_else(|| format!("Undo point is damaged: {}", dir.display()))?;
+    point.dir = dir;
+    Ok(Some(point))
+}
+
+// Keeps the files a successful install replaced as the installation's newest undo
+// point. Failing to keep it never fails the install.
+pub(crate) fn keep(install_location: &str, operation: JournalOperation, snapshot: InstallSnapshot, previous_preset: Option<InstalledPreset>) {
+    if let Err(e) = keep_in(&undo_root(install_location), install_location, operation, snapshot, previous_preset) {
+        println!("⚠ Failed to keep undo point: {}", e);
+    }
+}
+
+pub(crate) fn latest(install_location: &str) -> Result<Option<UndoPoint>, String> {
+    latest_in(&undo_root(install_location))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::fake::RecordingBackend;
+    use crate::transaction::replace_transaction;
+
+    #[tokio::test]
+    async fn keeps_replaced_files_and_prunes_old_points() {
+        let work = std::env::temp_dir().join(format!("brtx-test-{}", std::process::id())).join("undo");
+        let _ = fs::remove_dir_all(&work);
+        let materials = work.join("materials");
+        fs::create_dir_all(&materials).unwrap();
+        let stub = materials.join("RTXStub.material.bin");
+        fs::write(&stub, "version 0").unwrap();
+        let points = work.join("undo");
+
+        for version in 1..=UNDO_LIMIT + 2 {
+            let source = work.join(format!("{version}")).join("RTXStub.material.bin");
+            fs::create_dir_all(source.parent().unwrap()).unwrap();
+            fs::write(&source, format!("version {version}")).unwrap();
+            let (_, snapshot) = replace_transaction(&work.join("snapshots"), &RecordingBackend::default(), std::slice::from_ref(&stub), &[(source, stub.clone())]).await.unwrap();
+            keep_in(&points, "C:\\Minecraft", JournalOperation::Install, snapshot, None).unwrap();
+        }
+
+        assert_eq!(point_dirs(&points).len(), UNDO_LIMIT);
+        let point = latest_in(&points).unwrap().unwrap();
+        assert_eq!(point.targets(), vec![stub.clone()]);
+        let saved = point.saved_pairs();
+        assert_eq!(fs::read_to_string(&saved[0].0).unwrap(), format!("version {}", UNDO_LIMIT + 1));
+        point.discard();
+        assert_eq!(point_dirs(&points).len(), UNDO_LIMIT - 1);
+        assert!(latest_in(&work.join("absent")).unwrap().is_none());
+    }
+}
@@ -0,0 +1,139 @@
+// Post-install verification: read back each written file and compare it with its source
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::sha256_hex;
+
+// IObit and elevated copies can land shortly after the helper process exits
+const VERIFY_ATTEMPTS: u32 = 3;
+const VERIFY_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum VerificationStatus {
+    Verified,
+    Mismatch,
+    Missing,
+    // Verification turned off in the installer settings
+    Skipped,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct FileVerification {
+    pub file_name: String,
+    pub target_path: String,
+    pub status: VerificationStatus,
+    pub source_size: u64,
+    pub source_sha256: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_sha256: Option<String>,
+}
+
+pub(crate) fn verify_file(source: &Path, target: &Path, skip: bool) -> Result<FileVerification, String> {
+    let source_data = fs::read(source).map_err(|e| format!("Failed to read {}: {e}", source.display()))?;
+    let mut result = FileVerification {
+        file_name: target.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+        target_path: target.to_string_lossy().to_string(),
+        status: VerificationStatus::Skipped,
+        source_size: source_data.len() as u64,
+        source_sha256: sha256_hex(&source_data),
+        target_size: None,
+        target_sha256: None,
+    };
+    if skip {
+        return Ok(result);
+    }
+    let Ok(target_data) = fs::read(target) else {
+        result.status = VerificationStatus::Missing;
+        return Ok(result);
+    };
+    result.target_size = Some(target_data.len() as u64);
+    result.target_sha256 = Some(sha256_hex(&target_data));
+    result.status = if result.target_size == Some(result.source_size) && result.target_sha256.as_deref() == Some(&result.source_sha256) {
+        VerificationStatus::Verified
+    } else {
+        VerificationStatus::Mismatch
+    };
+    Ok(result)
+}
+
+// Verifies every (source, target) pair, retrying briefly while any target is still missing or stale
+pub(crate) async fn verify_files(pairs: &[(PathBuf, PathBuf)], skip: bool) -> Result<Vec<FileVerification>, String> {
+    let mut attempt = 1;
+    loop {
+        let results = pairs
+            .iter()
+            .map(|(source, target)| verify_file(source, target, skip))
+            .collect::<Result<Vec<_>, _>>()?;
+        if failures(&results).is_none() || attempt >= VERIFY_ATTEMPTS {
+            return Ok(results);
+        }
+        attempt += 1;
+        tokio::time::sleep(VERIFY_RETRY_DELAY).await;
+    }
+}
+
+// Describes the files that failed verification, if any
+pub(crate) fn failures(results: &[FileVerification]) -> Option<String> {
+    let failed: Vec<String> = results
+        .iter()
+        .filter_map(|r| match r.status {
+            VerificationStatus::Mismatch => Some(format!("{} (content differs from source)", r.file_name)),
+            VerificationStatus::Missing => Some(format!("{} (missing)", r.file_name)),
+            _ => None,
+        })
+        .collect();
+    if failed.is_empty() { None } else { Some(format!("Verification failed for: {}", failed.join(", "))) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("brtx-verify-test-{}", std::process::id())).join(name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn retries_until_a_late_copy_lands() {
+        let dir = scratch_dir("late");
+        let source = dir.join("source.bin");
+        let target = dir.join("RTXStub.material.bin");
+        fs::write(&source, "stub").unwrap();
+        fs::write(&target, "stale").unwrap();
+
+        let late = target.clone();
+        let writer = tokio::spawn(async move {
+            tokio::time::sleep(VERIFY_RETRY_DELAY / 2).await;
+            fs::write(late, "stub").unwrap();
+        });
+        let results = verify_files(&[(source, target)], false).await.unwrap();
+        writer.await.unwrap();
+        assert_eq!(results[0].status, VerificationStatus::Verified);
+    }
+
+    #[tokio::test]
+    async fn reports_missing_and_skipped_files() {
+        let dir = scratch_dir("missing");
+        let source = dir.join("source.bin");
+        fs::write(&source, "stub").unwrap();
+        let pairs = [(source.clone(), dir.join("RTXStub.material.bin"))];
+
+        let results = verify_files(&pairs, false).await.unwrap();
+        assert_eq!(results[0].status, VerificationStatus::Missing);
+        assert_eq!(failures(&results).unwrap(), "Verification failed for: RTXStub.material.bin (missing)");
+
+        let skipped = verify_files(&pairs, true).await.unwrap();
+        assert_eq!(skipped[0].status, VerificationStatus::Skipped);
+        assert!(skipped[0].target_sha256.is_none());
+        assert!(failures(&skipped).is_none());
+        assert!(verify_files(&[(dir.join("absent.bin"), dir.join("x"))], true).await.is_err());
+    }
+}
@@ -1,4 +1,5 @@
 import React, { memo, useCallback, useEffect, useMemo, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { getCurrentWebview } from "@tauri-apps/api/webview";
 
@@ -103,18 +104,23 @@ const App: React.FC = () => {
     let unlisten: (() => void) | undefined;
     (async () => {
       const webview = getCurrentWebview();
-      unlisten = await webview.onDragDropEvent((event) => {
+      unlisten = await webview.onDragDropEvent(async (event) => {
         if (event.payload.type === 'enter') {
           setIsDragging(true);
         } else if (event.payload.type === 'drop') {
           setIsDragging(false);
-          const paths = event.payload.paths || [];
-          for (const path of paths) {
-            if (path.toLowerCase().endsWith(".rtpack")) {
-              setRtpackPath(path);
-              setRtpackDialogOpen(true);
-              break;
+          // The backend recognises archives and folders by content, whatever the extension;
+          // open the first dropped path it can install from
+          for (const path of event.payload.paths || []) {
+            try {
+              await invoke('detect_material_source', { path });
+            } catch (e) {
+              console.warn(`Ignoring dropped ${path}:`, e);
+              continue;
             }
+            setRtpackPath(path);
+            setRtpackDialogOpen(true);
+            break;
           }
         } else if (event.payload.type === 'leave') {
           setIsDragging(false);
@@ -3,6 +3,7 @@ import { invoke } from "@tauri-apps/api/core";
 import Button from "./ui/Button";
 import { useAppStore } from "../store/appStore";
 import { useStatusStore } from "../store/statusStore";
+import { checkReport, CommandReport } from "../store/commandReport";
 import { X } from "lucide-react";
 
 interface DeepLinkDialogProps {
@@ -58,19 +59,19 @@ export const DeepLinkDialog: React.FC<DeepLinkDialogProps> = ({
     try {
       const selectedNames = Array.from(selectedInstallations);
       if (protocolData.protocol_type === "preset") {
-        await invoke<void>("download_preset_by_uuid", {
+        checkReport(await invoke<CommandReport>("download_preset_by_uuid", {
           uuid: protocolData.id,
           selectedNames,
-        });
+        }));
         addMessage({
           message: `Successfully installed preset ${protocolData.id}`,
           type: "success",
         });
       } else if (protocolData.protocol_type === "creator") {
-        await invoke<void>("download_creator_settings", {
+        checkReport(await invoke<CommandReport>("download_creator_settings", {
           settingsHash: protocolData.id,
           selectedNames,
-        });
+        }));
         addMessage({
           message: `Successfully installed creator settings ${protocolData.id.slice(
             0,
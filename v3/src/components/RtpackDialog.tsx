@@ -1,11 +1,21 @@
 import React, { useState, useEffect, useCallback } from "react";
 import { useTranslation } from "react-i18next";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { cx } from "classix";
 import Modal from "./ui/Modal";
 import Button from "./ui/Button";
 import { useAppStore } from "../store/appStore";
 import { useStatusStore } from "../store/statusStore";
+import { checkReport, CommandReport } from "../store/commandReport";
+
+interface ExtractProgress {
+  archive: string;
+  entry: string;
+  index: number;
+  total: number;
+  bytes: number;
+}
 
 interface RtpackDialogProps {
   isOpen: boolean;
@@ -25,6 +35,7 @@ export const RtpackDialog: React.FC<RtpackDialogProps> = ({
     Set<string>
   >(new Set());
   const [isInstalling, setIsInstalling] = useState(false);
+  const [extractProgress, setExtractProgress] = useState<ExtractProgress | null>(null);
 
   useEffect(() => {
     if (isOpen && installations.length === 0) {
@@ -32,6 +43,21 @@ export const RtpackDialog: React.FC<RtpackDialogProps> = ({
     }
   }, [isOpen, installations.length, refreshInstallations]);
 
+  useEffect(() => {
+    if (!isInstalling) {
+      setExtractProgress(null);
+      return;
+    }
+    const unlisten = listen<ExtractProgress>("extract-progress", (event) => {
+      if (event.payload.archive === rtpackPath) {
+        setExtractProgress(event.payload);
+      }
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [isInstalling, rtpackPath]);
+
   const handleInstallationToggle = useCallback((installLocation: string): void => {
     const newSet = new Set(selectedInstallations);
     if (newSet.has(installLocation)) {
@@ -43,10 +69,6 @@ export const RtpackDialog: React.FC<RtpackDialogProps> = ({
   }, [selectedInstallations]);
 
   const handleInstall = useCallback(async (): Promise<void> => {
-    if (!rtpackPath.toLowerCase().endsWith(".rtpack")) {
-      addMessage({ message: t("invalid_rtpack", "Invalid RTpack file"), type: "error" });
-      return;
-    }
     if (selectedInstallations.size === 0) {
       addMessage({
         message: t("status_select_installation_warning"),
@@ -57,11 +79,16 @@ export const RtpackDialog: React.FC<RtpackDialogProps> = ({
     setIsInstalling(true);
     try {
       addMessage({ message: t("status_installing_rtpack"), type: "loading" });
-      await invoke<void>("install_from_rtpack", {
-        rtpackPath,
+      const report = checkReport(await invoke<CommandReport>("import_material_source", {
+        sourcePath: rtpackPath,
         selectedNames: Array.from(selectedInstallations),
-      });
+      }));
       addMessage({ message: t("status_install_success"), type: "success" });
+      if (report.signature?.status === "signed_trusted") {
+        addMessage({ message: t("rtpack_signed_trusted", { publisher: report.signature.publisher, defaultValue: "Pack signed by {{publisher}}" }), type: "info" });
+      } else if (report.signature) {
+        addMessage({ message: t("rtpack_not_trusted", "Pack is not signed by a trusted publisher"), type: "info" });
+      }
       await refreshInstallations();
       onClose();
     } catch (error) {
@@ -191,7 +218,9 @@ export const RtpackDialog: React.FC<RtpackDialogProps> = ({
             disabled={selectedInstallations.size === 0 || isInstalling}
           >
             {isInstalling
-              ? t("installing")
+              ? extractProgress
+                ? `${t("installing")} (${extractProgress.index}/${extractProgress.total})`
+                : t("installing")
               : t("install_to_selected", { count: selectedInstallations.size })}
           </Button>
         </div>
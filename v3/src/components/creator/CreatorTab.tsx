@@ -5,6 +5,7 @@ import { open } from "@tauri-apps/plugin-dialog";
 import Button from "../ui/Button";
 import { useAppStore } from "../../store/appStore";
 import { useStatusStore } from "../../store/statusStore";
+import { checkReport, CommandReport } from "../../store/commandReport";
 import InstallationInstanceModal from "../installations/InstallationInstanceModal";
 import CreatorNameModal from "./CreatorNameModal";
 
@@ -58,12 +59,12 @@ export default function CreatorTab() {
       // Create a unique UUID for the creator preset using settings hash
       const creatorUuid = `creator-${settingsHash.trim()}`;
       
-      await invoke("download_creator_settings", {
+      checkReport(await invoke<CommandReport>("download_creator_settings", {
         settingsHash: settingsHash.trim(),
         selectedNames: pendingInstallData.selectedNames,
         presetName,
         uuid: creatorUuid,
-      });
+      }));
 
       addMessage({
         message: t("creator_install_success", { name: presetName }),
@@ -119,10 +120,10 @@ export default function CreatorTab() {
     try {
       addConsoleOutput(t("log_installing_material_preset", { name: presetName }));
       
-      await invoke("install_uploaded_materials", {
+      checkReport(await invoke<CommandReport>("install_uploaded_materials", {
         selectedNames: pendingMaterialData.selectedNames,
         presetName,
-      });
+      }));
 
       addMessage({
         message: t("creator_materials_install_success", { name: presetName }),
@@ -294,10 +295,10 @@ export default function CreatorTab() {
       const filename = selectedRtpack.split(/[\\\/]/).pop() || selectedRtpack;
       addConsoleOutput(t("log_installing_rtpack", { name: filename }));
       
-      await invoke("install_from_rtpack", {
+      checkReport(await invoke<CommandReport>("install_from_rtpack", {
         rtpackPath: selectedRtpack,
         selectedNames,
-      });
+      }));
 
       addMessage({
         message: t("creator_rtpack_install_success", { name: filename }),
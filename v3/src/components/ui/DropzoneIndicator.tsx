@@ -14,7 +14,7 @@ export default function DropzoneIndicator({ isDragging }: DropzoneIndicatorProps
       <div className="dropzone-overlay__glow">
         <div className="dropzone-overlay__content">
           <FileUp size={64} className="dropzone-overlay__icon" />
-          <p className="dropzone-overlay__text">Drop .rtpack file to install</p>
+          <p className="dropzone-overlay__text">Drop a .rtpack, .mcpack, .zip or folder to install</p>
         </div>
       </div>
     </div>
@@ -1,5 +1,6 @@
 import { create } from 'zustand';
 import { invoke } from '@tauri-apps/api/core';
+import { checkReport, CommandReport } from './commandReport';
 
 export interface Installation {
   FriendlyName: string;
@@ -147,7 +148,7 @@ export const useAppStore = create<AppState>((set, get) => ({
     const { addConsoleOutput } = get();
     try {
       addConsoleOutput(`Installing RTX DLSS to ${installPath}...`);
-      await invoke('install_dlss_for_selected', { selectedNames: [installPath] });
+      checkReport(await invoke<CommandReport>('install_dlss_for_selected', { selectedNames: [installPath] }));
       addConsoleOutput('RTX DLSS installed successfully');
     } catch (error) {
       const errorMsg = `Error installing RTX DLSS: ${error}`;
@@ -159,7 +160,7 @@ export const useAppStore = create<AppState>((set, get) => ({
     const { addConsoleOutput } = get();
     try {
       addConsoleOutput(`Updating options for ${installPath}...`);
-      await invoke('update_options_for_selected', { selectedNames: [installPath] });
+      checkReport(await invoke<CommandReport>('update_options_for_selected', { selectedNames: [installPath] }));
       addConsoleOutput('Options updated successfully');
     } catch (error) {
       const errorMsg = `Error updating options: ${error}`;
@@ -171,7 +172,8 @@ export const useAppStore = create<AppState>((set, get) => ({
     const { addConsoleOutput } = get();
     try {
       addConsoleOutput(`Creating backup for ${installPath}...`);
-      const backupDir = await invoke('backup_selected', { destDir: 'C:\\Users\\Public\\Documents', selectedNames: [installPath] });
+      const report = checkReport(await invoke<CommandReport>('backup_selected', { destDir: 'C:\\Users\\Public\\Documents', selectedNames: [installPath] }));
+      const backupDir = report.targets.map((target) => target.output).join(', ');
       addConsoleOutput(`Backup created successfully: ${backupDir}`);
     } catch (error) {
       const errorMsg = `Error creating backup: ${error}`;
@@ -183,7 +185,7 @@ export const useAppStore = create<AppState>((set, get) => ({
     const { addConsoleOutput } = get();
     try {
       addConsoleOutput(`Uninstalling RTX from ${installPaths.length} installation(s)...`);
-      await invoke('uninstall_rtx', { selectedNames: installPaths });
+      checkReport(await invoke<CommandReport>('uninstall_rtx', { selectedNames: installPaths }));
       addConsoleOutput('RTX uninstalled successfully');
     } catch (error) {
       const errorMsg = `Error uninstalling RTX: ${error}`;
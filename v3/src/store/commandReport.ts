@@ -0,0 +1,44 @@
+// Per-installation results returned by multi-target commands (see src-tauri/src/results.rs)
+export type TargetStatus = 'installed' | 'skipped_unknown' | 'failed' | 'rolled_back' | 'cancelled';
+
+export interface TargetResult {
+  install_location: string;
+  status: TargetStatus;
+  reason?: string;
+  output?: string;
+  // Problems that did not stop the command, e.g. a material version mismatch
+  warnings?: string[];
+}
+
+// Provenance of an installed .rtpack (see src-tauri/src/signing.rs)
+export type SignatureStatus =
+  | { status: 'signed_trusted'; publisher: string; public_key: string }
+  | { status: 'signed_unknown'; public_key: string }
+  | { status: 'unsigned' };
+
+export interface CommandReport {
+  targets: TargetResult[];
+  signature?: SignatureStatus;
+}
+
+// Throws a per-installation summary when any selected installation was not completed
+export function checkReport(report: CommandReport): CommandReport {
+  const problems = report.targets
+    .filter((target) => target.status !== 'installed')
+    .map((target) => {
+      switch (target.status) {
+        case 'skipped_unknown':
+          return `${target.install_location}: unknown installation, skipped`;
+        case 'rolled_back':
+          return `${target.install_location}: ${target.reason} (changes rolled back)`;
+        case 'cancelled':
+          return `${target.install_location}: cancelled before it was changed`;
+        default:
+          return `${target.install_location}: ${target.reason}`;
+      }
+    });
+  if (problems.length > 0) {
+    throw new Error(problems.join('\n'));
+  }
+  return report;
+}
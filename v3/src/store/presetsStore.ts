@@ -2,6 +2,7 @@ import { create } from 'zustand';
 import { invoke } from "@tauri-apps/api/core";
 import { useAppStore } from './appStore';
 import { useStatusStore } from './statusStore';
+import { checkReport, CommandReport } from './commandReport';
 
 interface PresetsStore {
   selectedPreset: string | null;
@@ -55,7 +56,7 @@ export const usePresetsStore = create<PresetsStore>((set) => ({
 
       for (const installPath of selectedInstallations) {
         addConsoleOutput(t("log_installing_to", { installPath }));
-        await invoke("download_and_install_pack", { uuid, selectedNames: [installPath] });
+        checkReport(await invoke<CommandReport>("download_and_install_pack", { uuid, selectedNames: [installPath] }));
         addConsoleOutput(t("log_installed_to", { installPath }));
       }
 
@@ -102,7 +103,7 @@ export const usePresetsStore = create<PresetsStore>((set) => ({
 
       for (const installPath of selectedInstallations) {
         addConsoleOutput(t("log_installing_to", { installPath }));
-        await invoke("download_and_install_pack", { uuid, selectedNames: [installPath] });
+        checkReport(await invoke<CommandReport>("download_and_install_pack", { uuid, selectedNames: [installPath] }));
         addConsoleOutput(t("log_installed_to", { installPath }));
       }
 